@@ -0,0 +1,196 @@
+use std::marker::PhantomData;
+use std::time::Instant;
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Fixed, Instance, Selector},
+    poly::Rotation,
+};
+
+use ff::{Field, PrimeField};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+// x^7 is a permutation of the bn256 scalar field, and ceil(log_7(p)) = 91 rounds are needed
+// for the degree of the whole chain to cover the field.
+const ROUNDS: usize = 91;
+
+// the round constants are public: anyone can regenerate them from this seed
+const ROUND_CONSTANTS_SEED: u64 = 7;
+
+struct TestCircuit<F: Field> {
+    _ph: PhantomData<F>,
+    preimage: Value<F>,
+}
+
+#[derive(Clone, Debug)]
+struct TestConfig<F: Field + Clone> {
+    _ph: PhantomData<F>,
+    q_round: Selector,
+    advice: Column<Advice>,
+    round_constant: Column<Fixed>,
+    instance: Column<Instance>,
+}
+
+fn round_constants<F: PrimeField>() -> Vec<F> {
+    let mut rng = ChaCha8Rng::seed_from_u64(ROUND_CONSTANTS_SEED);
+    (0..ROUNDS).map(|_| F::random(&mut rng)).collect()
+}
+
+/// MiMC round function: x -> (x + k)^7
+fn round<F: PrimeField>(x: F, k: F) -> F {
+    let t = x + k;
+    let t2 = t.square();
+    let t4 = t2.square();
+    t4 * t2 * t
+}
+
+/// Computes the hash chain outside of the circuit, to know which public output to expect.
+fn mimc<F: PrimeField>(preimage: F) -> F {
+    round_constants::<F>().into_iter().fold(preimage, round)
+}
+
+impl<F: PrimeField> TestCircuit<F> {
+    /// Lays out the whole chain in a single region, one row per round:
+    ///
+    /// | advice  | round_constant | q_round |
+    /// | x       | k_0            | 1       |
+    /// | x_1     | k_1            | 1       |
+    /// | ...     | ...            | ...     |
+    /// | x_R     |                | 0       |
+    fn hash_chain(
+        &self,
+        config: &<Self as Circuit<F>>::Config,
+        layouter: &mut impl Layouter<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let constants = round_constants::<F>();
+        layouter.assign_region(
+            || "mimc",
+            |mut region| {
+                let mut x = region.assign_advice(|| "preimage", config.advice, 0, || self.preimage)?;
+                for (i, k) in constants.iter().enumerate() {
+                    region.assign_fixed(|| "round constant", config.round_constant, i, || Value::known(*k))?;
+                    config.q_round.enable(&mut region, i)?;
+
+                    let next = x.value().map(|x| round(*x, *k));
+                    x = region.assign_advice(|| "round output", config.advice, i + 1, || next)?;
+                }
+                Ok(x)
+            },
+        )
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
+    type Config = TestConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        TestCircuit {
+            _ph: PhantomData,
+            preimage: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let q_round = meta.complex_selector();
+        let advice = meta.advice_column();
+        let round_constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
+
+        // next = (curr + k)^7 if q_round is 1
+        // together with the selector this is a degree 8 gate
+        meta.create_gate("mimc-round", |meta| {
+            let curr = meta.query_advice(advice, Rotation::cur());
+            let next = meta.query_advice(advice, Rotation::next());
+            let k = meta.query_fixed(round_constant, Rotation::cur());
+            let q_round = meta.query_selector(q_round);
+
+            let t = curr + k;
+            let t2 = t.clone() * t.clone();
+            let t4 = t2.clone() * t2.clone();
+            vec![q_round * (next - t4 * t2 * t)]
+        });
+
+        TestConfig {
+            _ph: PhantomData,
+            q_round,
+            advice,
+            round_constant,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let output = self.hash_chain(&config, &mut layouter)?;
+
+        // the preimage stays private, only the output of the last round is public
+        layouter.constrain_instance(output.cell(), config.instance, 0)?;
+        Ok(())
+    }
+}
+
+fn main() {
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    let mut meta = ConstraintSystem::<Fr>::default();
+    TestCircuit::<Fr>::configure(&mut meta);
+    println!("{} rounds, constraint system degree {}", ROUNDS, meta.degree());
+
+    let preimage = Fr::from(42);
+    let circuit = TestCircuit::<Fr> {
+        _ph: PhantomData,
+        preimage: Value::known(preimage),
+    };
+
+    let start = Instant::now();
+    let prover = MockProver::run(8, &circuit, vec![vec![mimc(preimage)]]).unwrap();
+    prover.verify().unwrap();
+    println!("MockProver took {:?}", start.elapsed());
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_should_prove_knowledge_of_preimage(){
+        use halo2_proofs::halo2curves::bn256::Fr;
+        use super::*;
+
+        let preimage = Fr::from(42);
+        let circuit = TestCircuit::<Fr> {
+            _ph: PhantomData,
+            preimage: Value::known(preimage),
+        };
+        let prover = MockProver::run(8, &circuit, vec![vec![mimc(preimage)]]).unwrap();
+        prover.verify().unwrap();
+    }
+
+    #[test]
+    fn test_should_not_accept_wrong_preimage(){
+        use halo2_proofs::halo2curves::bn256::Fr;
+        use super::*;
+
+        let circuit = TestCircuit::<Fr> {
+            _ph: PhantomData,
+            preimage: Value::known(Fr::from(43)),
+        };
+        let prover = MockProver::run(8, &circuit, vec![vec![mimc(Fr::from(42))]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_round_constants_are_deterministic(){
+        use halo2_proofs::halo2curves::bn256::Fr;
+        use super::*;
+
+        assert_eq!(round_constants::<Fr>(), round_constants::<Fr>());
+        assert_ne!(mimc(Fr::from(1)), mimc(Fr::from(2)));
+    }
+}
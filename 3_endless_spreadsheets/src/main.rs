@@ -1,3 +1,5 @@
+mod recurrence;
+
 use std::marker::PhantomData;
 
 use halo2_proofs::{
@@ -7,7 +9,9 @@ use halo2_proofs::{
     poly::Rotation,
 };
 
-use ff::Field;
+use ff::{Field, PrimeField};
+
+use recurrence::Recurrence;
 
 const STEPS: usize = 5;
 
@@ -97,6 +101,97 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
     }
 }
 
+/// A relation written in the recurrence DSL, see `recurrence.rs`.
+trait Relation {
+    const SOURCE: &'static str;
+}
+
+/// The "step" gate of `TestCircuit`, written as a recurrence.
+struct Step;
+
+impl Relation for Step {
+    const SOURCE: &'static str = "x[i+1] = x[i] + 1";
+}
+
+struct MulAddThree;
+
+impl Relation for MulAddThree {
+    const SOURCE: &'static str = "x[i+2] = x[i+1] * x[i] + 3";
+}
+
+/// Applies the gate compiled from `R::SOURCE` STEPS times. Only the initial values are part of
+/// the witness, the rest of the trace is generated from the relation itself.
+struct RecurrenceCircuit<F: Field, R: Relation> {
+    _ph: PhantomData<(F, R)>,
+    initial: Value<Vec<F>>,
+}
+
+#[derive(Clone, Debug)]
+struct RecurrenceConfig<F: Field + Clone> {
+    _ph: PhantomData<F>,
+    q_enable: Selector,
+    advice: Column<Advice>,
+    recurrence: Recurrence,
+}
+
+impl<F: PrimeField, R: Relation> Circuit<F> for RecurrenceCircuit<F, R> {
+    type Config = RecurrenceConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        RecurrenceCircuit {
+            _ph: PhantomData,
+            initial: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let q_enable = meta.complex_selector();
+        let advice = meta.advice_column();
+
+        let recurrence = Recurrence::parse(R::SOURCE).expect("invalid recurrence");
+        recurrence.create_gate(meta, advice, q_enable);
+
+        RecurrenceConfig {
+            _ph: PhantomData,
+            q_enable,
+            advice,
+            recurrence,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        // every application of the gate adds one term after the initial values
+        let rows = STEPS + config.recurrence.initial_values();
+        let trace = self.initial.as_ref().map(|initial| config.recurrence.trace(initial, rows));
+        trace.as_ref().error_if_known_and(|trace| trace.is_err())?;
+        let trace = trace.map(|trace| trace.unwrap_or_default());
+
+        layouter.assign_region(
+            || "steps",
+            |mut region| {
+                for i in 0..rows {
+                    region.assign_advice(
+                        || "assign advice",
+                        config.advice,
+                        i,
+                        || trace.as_ref().map(|trace| trace[i]),
+                    )?;
+                }
+                for i in 0..STEPS {
+                    config.q_enable.enable(&mut region, i)?;
+                }
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+}
+
 fn main() {
     use halo2_proofs::halo2curves::bn256::Fr;
     let circuit = TestCircuit::<Fr> {
@@ -112,4 +207,60 @@ fn main() {
     };
     let prover = MockProver::run(8, &circuit, vec![]).unwrap();
     prover.verify().unwrap();
+
+    // the same computation, with the gate compiled from the recurrence DSL
+    let circuit = RecurrenceCircuit::<Fr, Step> {
+        _ph: PhantomData,
+        initial: Value::known(vec![Fr::from(1)]),
+    };
+    let prover = MockProver::run(8, &circuit, vec![]).unwrap();
+    prover.verify().unwrap();
+
+    let circuit = RecurrenceCircuit::<Fr, MulAddThree> {
+        _ph: PhantomData,
+        initial: Value::known(vec![Fr::from(1), Fr::from(2)]),
+    };
+    let prover = MockProver::run(8, &circuit, vec![]).unwrap();
+    prover.verify().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_step_relation_matches_step_gate(){
+        use halo2_proofs::halo2curves::bn256::Fr;
+        use super::*;
+
+        let circuit = RecurrenceCircuit::<Fr, Step> {
+            _ph: PhantomData,
+            initial: Value::known(vec![Fr::from(1)]),
+        };
+        let prover = MockProver::run(8, &circuit, vec![]).unwrap();
+        prover.verify().unwrap();
+    }
+
+    #[test]
+    fn test_mul_add_three_relation(){
+        use halo2_proofs::halo2curves::bn256::Fr;
+        use super::*;
+
+        let circuit = RecurrenceCircuit::<Fr, MulAddThree> {
+            _ph: PhantomData,
+            initial: Value::known(vec![Fr::from(1), Fr::from(2)]),
+        };
+        let prover = MockProver::run(8, &circuit, vec![]).unwrap();
+        prover.verify().unwrap();
+    }
+
+    #[test]
+    fn test_should_not_accept_wrong_number_of_initial_values(){
+        use halo2_proofs::halo2curves::bn256::Fr;
+        use super::*;
+
+        let circuit = RecurrenceCircuit::<Fr, MulAddThree> {
+            _ph: PhantomData,
+            initial: Value::known(vec![Fr::from(1)]),
+        };
+        assert!(MockProver::run(8, &circuit, vec![]).is_err());
+    }
 }
@@ -0,0 +1,384 @@
+use halo2_proofs::{
+    plonk::{Advice, Column, ConstraintSystem, Expression, Selector},
+    poly::Rotation,
+};
+
+use ff::PrimeField;
+
+/// A relation over a single sequence, e.g. `x[i+2] = x[i+1] * x[i] + 3`.
+///
+/// Every occurrence of the sequence is stored by its offset relative to `i`. When turned into
+/// a gate, the smallest offset becomes `Rotation(0)`, so `x[i+2] = x[i+1] * x[i] + 3` queries
+/// the advice column at rotations 0, 1 and 2.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recurrence {
+    lhs: i32,
+    rhs: Term,
+    min_offset: i32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Term {
+    Var(i32),
+    Const(u64),
+    Neg(Box<Term>),
+    Add(Box<Term>, Box<Term>),
+    Sub(Box<Term>, Box<Term>),
+    Mul(Box<Term>, Box<Term>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecurrenceError {
+    /// The text could not be parsed; `position` is a byte offset into the source.
+    Parse { position: usize, message: String },
+    /// The text parsed, but does not describe a recurrence we can compile.
+    InvalidRelation(String),
+    /// The trace needs exactly one initial value per row spanned by the relation, minus one.
+    WrongNumberOfInitialValues { expected: usize, got: usize },
+}
+
+impl Recurrence {
+    pub fn parse(source: &str) -> Result<Self, RecurrenceError> {
+        let mut parser = Parser { source, position: 0, sequence: None };
+        let (lhs, index) = parser.var()?;
+        parser.expect('=')?;
+        let rhs = parser.expr()?;
+        parser.skip_whitespace();
+        if parser.position != source.len() {
+            return Err(parser.error("unexpected trailing input"));
+        }
+
+        let (name, _) = parser.sequence.unwrap_or_default();
+        let mut offsets = vec![];
+        rhs.offsets(&mut offsets);
+        if offsets.is_empty() {
+            return Err(RecurrenceError::InvalidRelation(format!(
+                "{}[{}{:+}] does not depend on earlier terms", name, index, lhs
+            )));
+        }
+        if offsets.iter().any(|offset| *offset >= lhs) {
+            return Err(RecurrenceError::InvalidRelation(format!(
+                "the right hand side may only use terms before {}[{}{:+}]", name, index, lhs
+            )));
+        }
+        let min_offset = offsets.into_iter().min().unwrap_or(lhs);
+        // every term is queried at `offset - min_offset`, which must fit in a rotation
+        if lhs.checked_sub(min_offset).is_none() {
+            return Err(RecurrenceError::InvalidRelation(format!(
+                "{}[{}{:+}] spans too many rows", name, index, lhs
+            )));
+        }
+
+        Ok(Self { lhs, rhs, min_offset })
+    }
+
+    /// Number of consecutive rows a single application of the relation touches.
+    pub fn window(&self) -> usize {
+        // `parse` already checked that the span fits in an i32
+        self.lhs.abs_diff(self.min_offset) as usize + 1
+    }
+
+    /// Number of terms that must be given before the relation can produce new ones.
+    pub fn initial_values(&self) -> usize {
+        self.window() - 1
+    }
+
+    /// Defines the gate `q * (lhs - rhs)`, with every term queried from `advice`.
+    pub fn create_gate<F: PrimeField>(
+        &self,
+        meta: &mut ConstraintSystem<F>,
+        advice: Column<Advice>,
+        q_enable: Selector,
+    ) {
+        meta.create_gate("recurrence", |meta| {
+            let q_enable = meta.query_selector(q_enable);
+            let lhs = meta.query_advice(advice, Rotation(self.lhs - self.min_offset));
+            let rhs = self.rhs.to_expression(&mut |offset| {
+                meta.query_advice(advice, Rotation(offset - self.min_offset))
+            });
+            vec![q_enable * (lhs - rhs)]
+        });
+    }
+
+    /// Extends `initial` with the relation until the trace holds `len` terms.
+    pub fn trace<F: PrimeField>(&self, initial: &[F], len: usize) -> Result<Vec<F>, RecurrenceError> {
+        if initial.len() != self.initial_values() {
+            return Err(RecurrenceError::WrongNumberOfInitialValues {
+                expected: self.initial_values(),
+                got: initial.len(),
+            });
+        }
+
+        let mut trace = initial.to_vec();
+        while trace.len() < len {
+            // the new term sits at offset `lhs`, so `x[i+offset]` is `lhs - offset` terms back
+            let n = trace.len();
+            let next = self.rhs.evaluate(&|offset| trace[n - (self.lhs - offset) as usize]);
+            trace.push(next);
+        }
+        trace.truncate(len);
+        Ok(trace)
+    }
+}
+
+impl Term {
+    fn offsets(&self, offsets: &mut Vec<i32>) {
+        match self {
+            Term::Var(offset) => offsets.push(*offset),
+            Term::Const(_) => {}
+            Term::Neg(term) => term.offsets(offsets),
+            Term::Add(lhs, rhs) | Term::Sub(lhs, rhs) | Term::Mul(lhs, rhs) => {
+                lhs.offsets(offsets);
+                rhs.offsets(offsets);
+            }
+        }
+    }
+
+    fn to_expression<F: PrimeField>(&self, query: &mut impl FnMut(i32) -> Expression<F>) -> Expression<F> {
+        match self {
+            Term::Var(offset) => query(*offset),
+            Term::Const(c) => Expression::Constant(F::from(*c)),
+            Term::Neg(term) => -term.to_expression(query),
+            Term::Add(lhs, rhs) => lhs.to_expression(query) + rhs.to_expression(query),
+            Term::Sub(lhs, rhs) => lhs.to_expression(query) - rhs.to_expression(query),
+            Term::Mul(lhs, rhs) => lhs.to_expression(query) * rhs.to_expression(query),
+        }
+    }
+
+    fn evaluate<F: PrimeField>(&self, value: &impl Fn(i32) -> F) -> F {
+        match self {
+            Term::Var(offset) => value(*offset),
+            Term::Const(c) => F::from(*c),
+            Term::Neg(term) => -term.evaluate(value),
+            Term::Add(lhs, rhs) => lhs.evaluate(value) + rhs.evaluate(value),
+            Term::Sub(lhs, rhs) => lhs.evaluate(value) - rhs.evaluate(value),
+            Term::Mul(lhs, rhs) => lhs.evaluate(value) * rhs.evaluate(value),
+        }
+    }
+}
+
+/// Recursive descent parser for
+///
+/// ```text
+/// relation := var '=' expr
+/// expr     := term (('+' | '-') term)*
+/// term     := factor ('*' factor)*
+/// factor   := '-' factor | number | var | '(' expr ')'
+/// var      := name '[' index (('+' | '-') number)? ']'
+/// ```
+///
+/// All variables must use the same sequence name and the same index name.
+struct Parser<'a> {
+    source: &'a str,
+    position: usize,
+    sequence: Option<(String, String)>,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &str) -> RecurrenceError {
+        RecurrenceError::Parse { position: self.position, message: message.to_string() }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.source[self.position..].chars().next() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.position += c.len_utf8();
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.source[self.position..].chars().next()
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.position += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), RecurrenceError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", expected)))
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String, RecurrenceError> {
+        self.skip_whitespace();
+        let start = self.position;
+        for (i, c) in self.source[start..].char_indices() {
+            if !(c.is_ascii_alphanumeric() || c == '_') || (i == 0 && c.is_ascii_digit()) {
+                break;
+            }
+            self.position = start + i + c.len_utf8();
+        }
+        if self.position == start {
+            return Err(self.error("expected a name"));
+        }
+        Ok(self.source[start..self.position].to_string())
+    }
+
+    fn number(&mut self) -> Result<u64, RecurrenceError> {
+        self.skip_whitespace();
+        let start = self.position;
+        let digits = self.source[start..].chars().take_while(|c| c.is_ascii_digit()).count();
+        if digits == 0 {
+            return Err(self.error("expected a number"));
+        }
+        self.position += digits;
+        self.source[start..self.position]
+            .parse()
+            .map_err(|_| RecurrenceError::Parse { position: start, message: "number too large".to_string() })
+    }
+
+    fn offset(&mut self) -> Result<i32, RecurrenceError> {
+        self.skip_whitespace();
+        let start = self.position;
+        i32::try_from(self.number()?)
+            .map_err(|_| RecurrenceError::Parse { position: start, message: "offset too large".to_string() })
+    }
+
+    fn var(&mut self) -> Result<(i32, String), RecurrenceError> {
+        let start = self.position;
+        let name = self.identifier()?;
+        self.expect('[')?;
+        let index = self.identifier()?;
+        let offset = if self.eat('+') {
+            self.offset()?
+        } else if self.eat('-') {
+            -self.offset()?
+        } else {
+            0
+        };
+        self.expect(']')?;
+
+        match &self.sequence {
+            None => self.sequence = Some((name, index.clone())),
+            Some(sequence) if *sequence == (name, index.clone()) => {}
+            Some((name, index)) => {
+                return Err(RecurrenceError::Parse {
+                    position: start,
+                    message: format!("expected {}[{}...]", name, index),
+                });
+            }
+        }
+        Ok((offset, index))
+    }
+
+    fn expr(&mut self) -> Result<Term, RecurrenceError> {
+        let mut lhs = self.term()?;
+        loop {
+            if self.eat('+') {
+                lhs = Term::Add(Box::new(lhs), Box::new(self.term()?));
+            } else if self.eat('-') {
+                lhs = Term::Sub(Box::new(lhs), Box::new(self.term()?));
+            } else {
+                return Ok(lhs);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Term, RecurrenceError> {
+        let mut lhs = self.factor()?;
+        while self.eat('*') {
+            lhs = Term::Mul(Box::new(lhs), Box::new(self.factor()?));
+        }
+        Ok(lhs)
+    }
+
+    fn factor(&mut self) -> Result<Term, RecurrenceError> {
+        match self.peek() {
+            Some('-') => {
+                self.position += 1;
+                Ok(Term::Neg(Box::new(self.factor()?)))
+            }
+            Some('(') => {
+                self.position += 1;
+                let inner = self.expr()?;
+                self.expect(')')?;
+                Ok(inner)
+            }
+            Some(c) if c.is_ascii_digit() => Ok(Term::Const(self.number()?)),
+            Some(_) => Ok(Term::Var(self.var()?.0)),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_parse_example_relation(){
+        use super::*;
+
+        let recurrence = Recurrence::parse("x[i+2] = x[i+1] * x[i] + 3").unwrap();
+        assert_eq!(recurrence.lhs, 2);
+        assert_eq!(recurrence.min_offset, 0);
+        assert_eq!(recurrence.window(), 3);
+        assert_eq!(recurrence.rhs, Term::Add(
+            Box::new(Term::Mul(Box::new(Term::Var(1)), Box::new(Term::Var(0)))),
+            Box::new(Term::Const(3)),
+        ));
+    }
+
+    #[test]
+    fn test_parse_negative_offsets_and_parentheses(){
+        use super::*;
+
+        let recurrence = Recurrence::parse("a[n] = -(a[n-1] - a[n-3]) * 2").unwrap();
+        assert_eq!(recurrence.lhs, 0);
+        assert_eq!(recurrence.min_offset, -3);
+        assert_eq!(recurrence.window(), 4);
+        assert_eq!(recurrence.initial_values(), 3);
+    }
+
+    #[test]
+    fn test_should_reject_malformed_relations(){
+        use super::*;
+
+        assert!(matches!(Recurrence::parse("x[i+1] = x[i] +"), Err(RecurrenceError::Parse { .. })));
+        assert!(matches!(Recurrence::parse("x[i+1] = y[i] + 1"), Err(RecurrenceError::Parse { .. })));
+        assert!(matches!(Recurrence::parse("x[i+1] = x[j] + 1"), Err(RecurrenceError::Parse { .. })));
+        assert!(matches!(Recurrence::parse("x[i+1] = x[i] 1"), Err(RecurrenceError::Parse { .. })));
+        assert!(matches!(Recurrence::parse("x[i] = x[i+1] + 1"), Err(RecurrenceError::InvalidRelation(_))));
+        assert!(matches!(Recurrence::parse("x[i] = 3"), Err(RecurrenceError::InvalidRelation(_))));
+    }
+
+    #[test]
+    fn test_should_reject_offsets_out_of_range(){
+        use super::*;
+
+        // 2^32 + 1 used to wrap around to 1
+        assert_eq!(
+            Recurrence::parse("x[i+4294967297] = x[i] + 1"),
+            Err(RecurrenceError::Parse { position: 4, message: "offset too large".to_string() })
+        );
+        // both offsets fit in an i32, their difference doesn't
+        let relation = format!("x[i+{}] = x[i-{}]", i32::MAX, i32::MAX);
+        assert!(matches!(Recurrence::parse(&relation), Err(RecurrenceError::InvalidRelation(_))));
+    }
+
+    #[test]
+    fn test_trace_from_initial_values(){
+        use halo2_proofs::halo2curves::bn256::Fr;
+        use super::*;
+
+        let recurrence = Recurrence::parse("x[i+2] = x[i+1] * x[i] + 3").unwrap();
+        let trace = recurrence.trace(&[Fr::from(1), Fr::from(2)], 5).unwrap();
+        // 1, 2, 2*1+3, 5*2+3, 13*5+3
+        let expected: Vec<Fr> = [1, 2, 5, 13, 68].into_iter().map(Fr::from).collect();
+        assert_eq!(trace, expected);
+
+        assert_eq!(
+            recurrence.trace(&[Fr::from(1)], 5),
+            Err(RecurrenceError::WrongNumberOfInitialValues { expected: 2, got: 1 })
+        );
+    }
+}
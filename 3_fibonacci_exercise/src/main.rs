@@ -1,9 +1,10 @@
+use std::cell::Cell;
 use std::marker::PhantomData;
 
 use halo2_proofs::{
     circuit::{Layouter, SimpleFloorPlanner, Value},
     dev::MockProver,
-    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Selector},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance, Selector},
     poly::Rotation,
 };
 
@@ -11,9 +12,17 @@ use ff::Field;
 
 const STEPS: usize = 10;
 
+// the two-column layout stores two consecutive terms per row
+const TWO_COLUMN_ROWS: usize = STEPS / 2;
+// `is_multiple_of` needs Rust 1.87
+#[allow(unknown_lints, clippy::manual_is_multiple_of)]
+const _: () = assert!(STEPS % 2 == 0, "the two-column layout needs an even number of steps");
+
 struct TestCircuit<F: Field> {
     _ph: PhantomData<F>,
     values: Value<Vec<F>>,
+    /// rows used by the region, set by `synthesize`
+    rows: Cell<usize>,
     // When creating a proof you assign the Values in the circuit struct with the witness and run
     // synthesis. Synthesis then assigns the values in the spreadsheet according to the Values in
     // the circuit struct.
@@ -24,6 +33,7 @@ struct TestConfig<F: Field + Clone> {
     _ph: PhantomData<F>,
     q_enable: Selector,
     advice: Column<Advice>,
+    instance: Column<Instance>,
 }
 
 impl<F: Field> Circuit<F> for TestCircuit<F> {
@@ -34,6 +44,7 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
         TestCircuit {
             _ph: PhantomData,
             values: Value::unknown(),
+            rows: Cell::new(0),
         }
     }
 
@@ -42,6 +53,9 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
         let q_enable = meta.complex_selector();
         let advice = meta.advice_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
 
         meta.create_gate("fib", |meta| {
             let current_row = meta.query_advice(advice, Rotation(0));
@@ -55,6 +69,7 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
             _ph: PhantomData,
             q_enable,
             advice,
+            instance,
         }
     }
 
@@ -64,7 +79,7 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
         config: Self::Config, //
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
-        layouter.assign_region(
+        let last = layouter.assign_region(
             || "steps", // Nombre de la region
             |mut region| {
                 for i in 0..(STEPS-2) {
@@ -92,28 +107,199 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
                     config.advice,
                     STEPS-1,
                     || self.values.as_ref().map(|values| values[STEPS-1]),
-                )?;
+                )
+            },
+        )?;
+        self.rows.set(last.cell().row_offset + 1);
+
+        // expose F(STEPS-1) as the first public input
+        layouter.constrain_instance(last.cell(), config.instance, 0)?;
+        Ok(())
+    }
+}
+
+/// Same sequence as `TestCircuit`, but laid out over two advice columns `a` and `b`.
+/// Row `i` holds the terms `F(2i)` and `F(2i+1)`, so every row advances the sequence by two
+/// terms and only half of the rows are needed.
+struct TwoColumnCircuit<F: Field> {
+    _ph: PhantomData<F>,
+    values: Value<Vec<F>>,
+    /// rows used by the region, set by `synthesize`
+    rows: Cell<usize>,
+}
+
+#[derive(Clone, Debug)]
+struct TwoColumnConfig<F: Field + Clone> {
+    _ph: PhantomData<F>,
+    q_enable: Selector,
+    a: Column<Advice>,
+    b: Column<Advice>,
+    instance: Column<Instance>,
+}
+
+impl<F: Field> Circuit<F> for TwoColumnCircuit<F> {
+    type Config = TwoColumnConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        TwoColumnCircuit {
+            _ph: PhantomData,
+            values: Value::unknown(),
+            rows: Cell::new(0),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let q_enable = meta.complex_selector();
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(b);
+        meta.enable_equality(instance);
+
+        // | a      | b      |
+        // | F(2i)  | F(2i+1)|
+        // | F(2i+2)| F(2i+3)|
+        meta.create_gate("fib-two-columns", |meta| {
+            let a_cur = meta.query_advice(a, Rotation::cur());
+            let b_cur = meta.query_advice(b, Rotation::cur());
+            let a_next = meta.query_advice(a, Rotation::next());
+            let b_next = meta.query_advice(b, Rotation::next());
+            let q_enable = meta.query_selector(q_enable);
+            vec![
+                q_enable.clone() * (a_next.clone() - a_cur - b_cur.clone()),
+                q_enable * (b_next - a_next - b_cur),
+            ]
+        });
+
+        TwoColumnConfig {
+            _ph: PhantomData,
+            q_enable,
+            a,
+            b,
+            instance,
+        }
+    }
 
-                Ok(())
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let last = layouter.assign_region(
+            || "steps",
+            |mut region| {
+                let mut last = None;
+                for i in 0..TWO_COLUMN_ROWS {
+                    region.assign_advice(
+                        || "assign a",
+                        config.a,
+                        i,
+                        || self.values.as_ref().map(|values| values[2 * i]),
+                    )?;
+                    last = Some(region.assign_advice(
+                        || "assign b",
+                        config.b,
+                        i,
+                        || self.values.as_ref().map(|values| values[2 * i + 1]),
+                    )?);
+
+                    // the last row has no successor to constrain
+                    if i + 1 < TWO_COLUMN_ROWS {
+                        config.q_enable.enable(&mut region, i)?;
+                    }
+                }
+                last.ok_or(Error::Synthesis)
             },
         )?;
+        self.rows.set(last.cell().row_offset + 1);
+
+        // expose F(STEPS-1) as the first public input
+        layouter.constrain_instance(last.cell(), config.instance, 0)?;
         Ok(())
     }
 }
 
+/// Returns the first `n` terms of the Fibonacci sequence, starting at F(0) = 0.
+fn fibonacci<F: Field>(n: usize) -> Vec<F> {
+    let mut fib: Vec<F> = vec![F::ZERO, F::ONE];
+    while fib.len() < n {
+        let new = fib[fib.len() - 1] + fib[fib.len() - 2];
+        fib.push(new);
+    }
+    fib.truncate(n);
+    fib
+}
+
 fn main() {
     use halo2_proofs::halo2curves::bn256::Fr;
 
-    let mut fib: Vec<Fr> = vec![Fr::from(0), Fr::from(1)];
-    for i in 1..STEPS {
-        let new = fib[i] + fib[i-1];
-        fib.push(new);
-    }
+    let fib: Vec<Fr> = fibonacci(STEPS);
+    let public_inputs = vec![fib[STEPS-1]];
 
-    let circuit = TestCircuit::<Fr> {
+    let single = TestCircuit::<Fr> {
+        _ph: PhantomData,
+        values: Value::known(fib.clone()),
+        rows: Cell::new(0),
+    };
+    let prover = MockProver::run(8, &single, vec![public_inputs.clone()]).unwrap();
+    prover.verify().unwrap();
+
+    let two_columns = TwoColumnCircuit::<Fr> {
         _ph: PhantomData,
         values: Value::known(fib),
+        rows: Cell::new(0),
     };
-    let prover = MockProver::run(8, &circuit, vec![]).unwrap();
+    let prover = MockProver::run(8, &two_columns, vec![public_inputs]).unwrap();
     prover.verify().unwrap();
+
+    println!("F({}): single column uses {} rows, two columns use {} rows",
+             STEPS-1, single.rows.get(), two_columns.rows.get());
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_both_layouts_give_the_same_result(){
+        use halo2_proofs::halo2curves::bn256::Fr;
+        use super::*;
+
+        let fib: Vec<Fr> = fibonacci(STEPS);
+        let expected = vec![fib[STEPS-1]];
+
+        let single = TestCircuit::<Fr> {
+            _ph: PhantomData,
+            values: Value::known(fib.clone()),
+            rows: Cell::new(0),
+        };
+        let prover = MockProver::run(8, &single, vec![expected.clone()]).unwrap();
+        prover.verify().unwrap();
+
+        let two_columns = TwoColumnCircuit::<Fr> {
+            _ph: PhantomData,
+            values: Value::known(fib),
+            rows: Cell::new(0),
+        };
+        let prover = MockProver::run(8, &two_columns, vec![expected]).unwrap();
+        prover.verify().unwrap();
+
+        // measured from the regions, not from the constants
+        println!("F({}): single column uses {} rows, two columns use {} rows",
+                 STEPS-1, single.rows.get(), two_columns.rows.get());
+        assert_eq!((single.rows.get(), two_columns.rows.get()), (STEPS, STEPS / 2));
+    }
+
+    #[test]
+    fn test_two_columns_should_not_accept_wrong_result(){
+        use halo2_proofs::halo2curves::bn256::Fr;
+        use super::*;
+
+        let circuit = TwoColumnCircuit::<Fr> {
+            _ph: PhantomData,
+            values: Value::known(fibonacci(STEPS)),
+            rows: Cell::new(0),
+        };
+        let prover = MockProver::run(8, &circuit, vec![vec![Fr::from(35)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
 }
@@ -3,14 +3,13 @@ use std::marker::PhantomData;
 use halo2_proofs::{
     circuit::{Layouter, SimpleFloorPlanner, Value},
     dev::MockProver,
-    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Selector},
+    plonk::{Circuit, ConstraintSystem, Error, Selector},
     poly::Rotation,
 };
 
 use ff::Field;
-use halo2_proofs::circuit::AssignedCell;
 
-const STEPS: usize = 10;
+use arithmetic::{ArithmeticChip, ArithmeticConfig, ArithmeticInstructions};
 
 struct TestCircuit<F: Field> {
     _ph: PhantomData<F>,
@@ -21,78 +20,8 @@ struct TestCircuit<F: Field> {
 #[derive(Clone, Debug)]
 struct TestConfig<F: Field + Clone> {
     _ph: PhantomData<F>,
-    q_mul: Selector,
+    arithmetic: ArithmeticConfig,
     q_compare: Selector,
-    advice: Column<Advice>,
-}
-
-impl<F: Field> TestCircuit<F> {
-    /// This region occupies 3 rows.
-    /// La función mul lo que hace es recibir 2 celdas (más objetos necesarios para la construccion
-    /// de la traza) y devolver una tercera celda con el producto de las 2 primeras.
-    /// En este lugar es que se hace la multiplicación real de valores.
-    fn mul(
-        config: &<Self as Circuit<F>>::Config,
-        layouter: &mut impl Layouter<F>,
-        lhs: AssignedCell<F, F>,
-        rhs: AssignedCell<F, F>,
-    ) -> Result<AssignedCell<F, F>, Error> {
-        layouter.assign_region(
-            || "mul",
-            |mut region| {
-                let v0 = lhs.value().cloned();
-                let v1 = rhs.value().cloned();
-                let v2 =
-                    v0 //
-                        .and_then(|v0| v1.and_then(|v1| Value::known(v0 * v1)));
-
-                let w0 = region.assign_advice(
-                    || "assign w0", //
-                    config.advice,
-                    0,
-                    || v0,
-                )?;
-
-                let w1 = region.assign_advice(
-                    || "assign w1", //
-                    config.advice,
-                    1,
-                    || v1,
-                )?;
-
-                let w2 = region.assign_advice(
-                    || "assign w2", //
-                    config.advice,
-                    2,
-                    || v2,
-                )?;
-
-                // Here it's enforced the multiplication constraint
-                config.q_mul.enable(&mut region, 0)?;
-                Ok(w2)
-            },
-        )
-    }
-
-    /// This region occupies 1 row. Esta es una función auxiliar para crear una región unitaria
-    /// (una única fila)
-    fn unconstrained(
-        config: &<Self as Circuit<F>>::Config,
-        layouter: &mut impl Layouter<F>,
-        value: Value<F>,
-    ) -> Result<AssignedCell<F, F>, Error> {
-        layouter.assign_region(
-            || "free variable",
-            |mut region| {
-                region.assign_advice(
-                    || "assign w0",
-                    config.advice,
-                    0,
-                    || value,
-                )
-            },
-        )
-    }
 }
 
 impl<F: Field> Circuit<F> for TestCircuit<F> {
@@ -108,18 +37,12 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        let q_enable = meta.complex_selector();
         let q_compare = meta.complex_selector();
         let advice = meta.advice_column();
+        let instance = meta.instance_column();
 
-        // define a new gate:
-        meta.create_gate("vertical-mul", |meta| {
-            let w0 = meta.query_advice(advice, Rotation(0));
-            let w1 = meta.query_advice(advice, Rotation(1));
-            let w3 = meta.query_advice(advice, Rotation(2));
-            let q_enable = meta.query_selector(q_enable);
-            vec![q_enable * (w0 * w1 - w3)]
-        });
+        // the "vertical-mul" gate lives in the chip
+        let arithmetic = ArithmeticChip::configure(meta, advice, Some(instance));
 
         meta.create_gate("compare", |meta| {
             let expected_result = meta.query_advice(advice, Rotation(0));
@@ -130,9 +53,8 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
 
         TestConfig {
             _ph: PhantomData,
-            q_mul: q_enable,
+            arithmetic,
             q_compare,
-            advice,
         }
     }
 
@@ -143,54 +65,34 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
         config: Self::Config, //
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
+        let chip = ArithmeticChip::<F>::construct(config.arithmetic.clone());
+
         // create a new free variable
-        let a = TestCircuit::<F>::unconstrained(
-            &config,
-            &mut layouter,
-            self.secret.clone(),
-        )?;
+        let a = chip.load_private(&mut layouter, self.secret)?;
 
         // do a few multiplications
-        let a2 = TestCircuit::<F>::mul(
-            &config, //
-            &mut layouter,
-            a.clone(),
-            a.clone(),
-        )?;
-        let a3 = TestCircuit::<F>::mul(
-            &config, //
-            &mut layouter,
-            a2.clone(),
-            a.clone(),
-        )?;
-        let a5 = TestCircuit::<F>::mul(
-            &config, //
-            &mut layouter,
-            a3.clone(),
-            a2.clone(),
-        )?;
-
+        let a2 = chip.mul(&mut layouter, &a, &a)?;
+        let a3 = chip.mul(&mut layouter, &a2, &a)?;
+        let a5 = chip.mul(&mut layouter, &a3, &a2)?;
 
-        let _ = layouter.assign_region(
+        let exp_result = layouter.assign_region(
             || "expected result",
             |mut region| {
                 let exp_result = region.assign_advice(
                     || "expected_result",
-                    config.advice,
+                    config.arithmetic.advice,
                     0,
-                    || self.assurance.clone(),
-                );
-                let copied_result = region.assign_advice(
-                    || "copied_result",
-                    config.advice,
-                    1,
-                    || a5.value().cloned(),
-                );
+                    || self.assurance,
+                )?;
+                a5.copy_advice(|| "copied_result", &mut region, config.arithmetic.advice, 1)?;
 
                 config.q_compare.enable(&mut region, 0)?;
-                exp_result
+                Ok(exp_result)
             },
-        );
+        )?;
+
+        // el resultado esperado es público
+        chip.expose_public(&mut layouter, &exp_result, 0)?;
 
         Ok(())
     }
@@ -204,6 +106,114 @@ fn main() {
         secret: Value::known(Fr::from(2)),
         assurance: Value::known(Fr::from(32))
     };
-    let prover = MockProver::run(8, &circuit, vec![]).unwrap();
+    let prover = MockProver::run(8, &circuit, vec![vec![Fr::from(32)]]).unwrap();
     prover.verify().unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Same layout as `TestCircuit`, but the prover cheats in the region computing a3: the copy
+    /// of a2 holds a forged value. The "vertical-mul" gate of that region is still satisfied,
+    /// so only the copy constraint between the regions can catch it.
+    struct TamperedCircuit<F: Field> {
+        secret: Value<F>,
+        forged_a2: Value<F>,
+        assurance: Value<F>,
+    }
+
+    impl<F: Field> Circuit<F> for TamperedCircuit<F> {
+        type Config = TestConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            TamperedCircuit {
+                secret: Value::unknown(),
+                forged_a2: Value::unknown(),
+                assurance: Value::unknown(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            TestCircuit::<F>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = ArithmeticChip::<F>::construct(config.arithmetic.clone());
+            let advice = config.arithmetic.advice;
+            let a = chip.load_private(&mut layouter, self.secret)?;
+            let a2 = chip.mul(&mut layouter, &a, &a)?;
+
+            let a3 = layouter.assign_region(
+                || "tampered mul",
+                |mut region| {
+                    let w0 = region.assign_advice(|| "forged w0", advice, 0, || self.forged_a2)?;
+                    region.constrain_equal(a2.cell(), w0.cell())?;
+                    let w1 = a.copy_advice(|| "assign w1", &mut region, advice, 1)?;
+
+                    let v2 = w0.value().cloned() * w1.value().cloned();
+                    let w2 = region.assign_advice(|| "assign w2", advice, 2, || v2)?;
+                    config.arithmetic.q_mul.enable(&mut region, 0)?;
+                    Ok(w2)
+                },
+            )?;
+            let a5 = chip.mul(&mut layouter, &a3, &a2)?;
+
+            let exp_result = layouter.assign_region(
+                || "expected result",
+                |mut region| {
+                    let exp_result = region.assign_advice(|| "expected_result", advice, 0, || self.assurance)?;
+                    a5.copy_advice(|| "copied_result", &mut region, advice, 1)?;
+                    config.q_compare.enable(&mut region, 0)?;
+                    Ok(exp_result)
+                },
+            )?;
+            chip.expose_public(&mut layouter, &exp_result, 0)
+        }
+    }
+
+    #[test]
+    fn test_should_compute_fifth_power(){
+        use halo2_proofs::halo2curves::bn256::Fr;
+
+        let circuit = TestCircuit::<Fr> {
+            _ph: PhantomData,
+            secret: Value::known(Fr::from(3)),
+            assurance: Value::known(Fr::from(243)),
+        };
+        let prover = MockProver::run(8, &circuit, vec![vec![Fr::from(243)]]).unwrap();
+        prover.verify().unwrap();
+    }
+
+    #[test]
+    fn test_should_not_accept_wrong_public_result(){
+        use halo2_proofs::halo2curves::bn256::Fr;
+
+        let circuit = TestCircuit::<Fr> {
+            _ph: PhantomData,
+            secret: Value::known(Fr::from(2)),
+            assurance: Value::known(Fr::from(32)),
+        };
+        let prover = MockProver::run(8, &circuit, vec![vec![Fr::from(31)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_should_not_accept_tampered_intermediate(){
+        use halo2_proofs::halo2curves::bn256::Fr;
+
+        // a = 2, a2 = 4, but the prover claims a3 = 5 * 2 = 10 and a5 = 10 * 4 = 40
+        let circuit = TamperedCircuit::<Fr> {
+            secret: Value::known(Fr::from(2)),
+            forged_a2: Value::known(Fr::from(5)),
+            assurance: Value::known(Fr::from(40)),
+        };
+        let prover = MockProver::run(8, &circuit, vec![vec![Fr::from(40)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
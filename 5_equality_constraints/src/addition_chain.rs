@@ -0,0 +1,183 @@
+/// An addition chain for `n` is a sequence `1 = c_0 < c_1 < ... < c_r = n` where every element
+/// is the sum of two earlier ones (possibly the same one twice). Read as exponents, it is a
+/// recipe to compute `a^n` with `r` multiplications: `a^(c_i + c_j) = a^c_i * a^c_j`.
+///
+/// `TestCircuit` computes `a^5` with the chain `1, 2, 3, 5`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdditionChain {
+    elements: Vec<u64>,
+    /// `steps[k] = (i, j)` means `elements[k + 1] = elements[i] + elements[j]`
+    steps: Vec<(usize, usize)>,
+}
+
+// Above this the exhaustive search gets slow, and we fall back to the binary method.
+const SEARCH_LIMIT: u64 = 1 << 12;
+
+impl AdditionChain {
+    /// The chain used by square-and-multiply: scanning the bits of `n` from the most significant
+    /// one, double for every bit and add 1 for every bit set.
+    pub fn binary(n: u64) -> Self {
+        assert!(n > 0, "there is no addition chain for 0");
+        let mut chain = Self { elements: vec![1], steps: vec![] };
+        for bit in (0..(63 - n.leading_zeros())).rev() {
+            chain.push(chain.last_index(), chain.last_index());
+            if (n >> bit) & 1 == 1 {
+                chain.push(chain.last_index(), 0);
+            }
+        }
+        chain
+    }
+
+    /// A shortest addition chain for `n`, found by iterative deepening. Exponents larger than
+    /// `SEARCH_LIMIT` use the binary method instead.
+    pub fn shortest(n: u64) -> Self {
+        assert!(n > 0, "there is no addition chain for 0");
+        if n > SEARCH_LIMIT {
+            return Self::binary(n);
+        }
+
+        // no chain can be shorter than the number of doublings needed to reach n
+        let mut limit = (63 - n.leading_zeros()) as usize;
+        loop {
+            let mut chain = Self { elements: vec![1], steps: vec![] };
+            if chain.search(n, limit) {
+                return chain;
+            }
+            limit += 1;
+        }
+    }
+
+    /// A chain given by its steps, e.g. one that isn't the shortest. `None` if a step uses an
+    /// element that doesn't exist yet.
+    pub fn from_steps(steps: &[(usize, usize)]) -> Option<Self> {
+        let mut chain = Self { elements: vec![1], steps: vec![] };
+        for (i, j) in steps {
+            if *i > chain.last_index() || *j > chain.last_index() {
+                return None;
+            }
+            chain.push(*i, *j);
+        }
+        Some(chain)
+    }
+
+    /// The exponent the chain reaches.
+    pub fn target(&self) -> u64 {
+        self.elements[self.last_index()]
+    }
+
+    fn search(&mut self, n: u64, limit: usize) -> bool {
+        let last = self.elements[self.last_index()];
+        if last == n {
+            return true;
+        }
+        let remaining = limit - self.len();
+        // even doubling at every remaining step can't reach n
+        if remaining == 0 || (last as u128) << remaining < n as u128 {
+            return false;
+        }
+
+        // try the largest sums first, they get closer to n faster
+        let mut tried = vec![];
+        for i in (0..self.elements.len()).rev() {
+            for j in (0..=i).rev() {
+                let next = self.elements[i] + self.elements[j];
+                if next <= last || next > n || tried.contains(&next) {
+                    continue;
+                }
+                tried.push(next);
+
+                self.push(i, j);
+                if self.search(n, limit) {
+                    return true;
+                }
+                self.elements.pop();
+                self.steps.pop();
+            }
+        }
+        false
+    }
+
+    fn push(&mut self, i: usize, j: usize) {
+        self.elements.push(self.elements[i] + self.elements[j]);
+        self.steps.push((i, j));
+    }
+
+    fn last_index(&self) -> usize {
+        self.elements.len() - 1
+    }
+
+    /// Number of multiplications needed to follow the chain.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn steps(&self) -> &[(usize, usize)] {
+        &self.steps
+    }
+
+    /// Multiplications saved by `shortest(n)` compared to `binary(n)`.
+    pub fn saved_multiplications(n: u64) -> usize {
+        Self::binary(n).len() - Self::shortest(n).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_valid(chain: &AdditionChain, n: u64) {
+        assert_eq!(chain.elements[0], 1);
+        assert_eq!(*chain.elements.last().unwrap(), n);
+        for (k, (i, j)) in chain.steps().iter().enumerate() {
+            assert!(*i <= k && *j <= k);
+            assert_eq!(chain.elements[k + 1], chain.elements[*i] + chain.elements[*j]);
+        }
+    }
+
+    #[test]
+    fn test_shortest_chain_lengths(){
+        // known values of l(n), the length of a shortest addition chain for n
+        for (n, expected) in [(1, 0), (2, 1), (3, 2), (5, 3), (15, 5), (23, 6), (127, 10), (191, 11)] {
+            let chain = AdditionChain::shortest(n);
+            assert_valid(&chain, n);
+            assert_eq!(chain.len(), expected, "l({})", n);
+        }
+    }
+
+    #[test]
+    fn test_chain_from_steps(){
+        // 1, 2, 3, 4, 7
+        let chain = AdditionChain::from_steps(&[(0, 0), (1, 0), (1, 1), (2, 3)]).unwrap();
+        assert_valid(&chain, 7);
+        assert_eq!(chain.target(), 7);
+        assert_eq!(AdditionChain::from_steps(&[(0, 0), (2, 0)]), None);
+    }
+
+    #[test]
+    fn test_binary_chain(){
+        for n in 1..200 {
+            assert_valid(&AdditionChain::binary(n), n);
+        }
+        // 5 = 101b: square, square, multiply
+        assert_eq!(AdditionChain::binary(5).elements, vec![1, 2, 4, 5]);
+        assert_eq!(AdditionChain::binary(15).len(), 6);
+    }
+
+    #[test]
+    fn test_shortest_is_never_longer_than_binary(){
+        for n in 1..300 {
+            let chain = AdditionChain::shortest(n);
+            assert_valid(&chain, n);
+            assert!(chain.len() <= AdditionChain::binary(n).len());
+        }
+        assert_eq!(AdditionChain::saved_multiplications(5), 0);
+        assert_eq!(AdditionChain::saved_multiplications(15), 1);
+        assert_eq!(AdditionChain::saved_multiplications(127), 2);
+    }
+
+    #[test]
+    fn test_large_exponent_falls_back_to_binary(){
+        let n = SEARCH_LIMIT + 1;
+        assert_eq!(AdditionChain::shortest(n), AdditionChain::binary(n));
+    }
+}
@@ -0,0 +1,141 @@
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
+};
+
+use ff::Field;
+
+use arithmetic::{ArithmeticChip, ArithmeticInstructions};
+
+/// Computes `base^exponent` with square-and-multiply on top of `ArithmeticChip`. Every
+/// multiplication is its own 3-row region, and its factors are copied from the previous step,
+/// so the whole chain is linked by copy constraints.
+#[derive(Clone, Debug)]
+pub struct ExpChip<F: Field> {
+    arithmetic: ArithmeticChip<F>,
+}
+
+impl<F: Field> ExpChip<F> {
+    pub fn new_for_advice(
+        meta: &mut ConstraintSystem<F>,
+        advice: Column<Advice>,
+        instance: Column<Instance>,
+    ) -> Self {
+        let config = ArithmeticChip::configure(meta, advice, Some(instance));
+        Self { arithmetic: ArithmeticChip::construct(config) }
+    }
+
+    pub fn load_private(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.arithmetic.load_private(layouter, value)
+    }
+
+    /// The exponent shapes the circuit (how many regions there are), so it is public: the
+    /// verifier must use the same one. It can be a constant or a parameter chosen at runtime,
+    /// as long as both parties agree on it.
+    ///
+    /// Scans the bits of the exponent from the most significant one: every bit squares the
+    /// accumulator, and every bit set to 1 multiplies it by the base afterwards.
+    pub fn pow(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        base: &AssignedCell<F, F>,
+        exponent: u64,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        // a^0 = 1 would need a constant cell, which this chip doesn't have
+        if exponent == 0 {
+            return Err(Error::Synthesis);
+        }
+
+        let mut acc = base.clone();
+        for bit in (0..(63 - exponent.leading_zeros())).rev() {
+            acc = self.arithmetic.mul(layouter, &acc, &acc)?;
+            if (exponent >> bit) & 1 == 1 {
+                acc = self.arithmetic.mul(layouter, &acc, base)?;
+            }
+        }
+        Ok(acc)
+    }
+
+    pub fn expose_public(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        self.arithmetic.expose_public(layouter, cell, row)
+    }
+}
+
+/// Proves knowledge of a secret `a` such that `a^exponent` is the first public input.
+pub struct ExpCircuit<F: Field> {
+    pub secret: Value<F>,
+    pub exponent: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct ExpConfig<F: Field + Clone> {
+    exp_chip: ExpChip<F>,
+}
+
+impl<F: Field> Circuit<F> for ExpCircuit<F> {
+    type Config = ExpConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        ExpCircuit {
+            secret: Value::unknown(),
+            exponent: self.exponent,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = meta.advice_column();
+        let instance = meta.instance_column();
+        let exp_chip = ExpChip::new_for_advice(meta, advice, instance);
+        ExpConfig { exp_chip }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = config.exp_chip;
+        let a = chip.load_private(&mut layouter, self.secret)?;
+        let result = chip.pow(&mut layouter, &a, self.exponent)?;
+        chip.expose_public(&mut layouter, &result, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+    use crate::tests::verify;
+
+    #[test]
+    fn test_should_compute_powers(){
+        assert!(verify(&ExpCircuit { secret: Value::known(Fr::from(2)), exponent: 5 }, vec![Fr::from(32)]).is_ok());
+        assert!(verify(&ExpCircuit { secret: Value::known(Fr::from(3)), exponent: 13 }, vec![Fr::from(1594323)]).is_ok());
+        assert!(verify(&ExpCircuit { secret: Value::known(Fr::from(7)), exponent: 1 }, vec![Fr::from(7)]).is_ok());
+        assert!(verify(&ExpCircuit { secret: Value::known(Fr::from(2)), exponent: 63 }, vec![Fr::from(1u64 << 63)]).is_ok());
+    }
+
+    #[test]
+    fn test_should_not_accept_wrong_result(){
+        assert!(verify(&ExpCircuit { secret: Value::known(Fr::from(2)), exponent: 5 }, vec![Fr::from(31)]).is_err());
+        assert!(verify(&ExpCircuit { secret: Value::known(Fr::from(2)), exponent: 6 }, vec![Fr::from(32)]).is_err());
+    }
+
+    #[test]
+    fn test_should_reject_zero_exponent(){
+        let circuit = ExpCircuit::<Fr> { secret: Value::known(Fr::from(2)), exponent: 0 };
+        assert!(MockProver::run(8, &circuit, vec![vec![Fr::ONE]]).is_err());
+    }
+}
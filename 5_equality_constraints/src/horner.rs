@@ -0,0 +1,277 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Fixed, Instance, Selector},
+    poly::Rotation,
+};
+
+use ff::Field;
+
+/// Evaluates a polynomial with Horner's rule, `P(x) = (...((c_0 * x + c_1) * x + c_2) ...) + c_n`,
+/// with the coefficients (highest degree first) in a fixed column, so they are part of the
+/// circuit and known to the verifier. One row per coefficient:
+///
+/// | acc                  | x   | c   | q_init | q_horner |
+/// | c_0                  | x   | c_0 | 1      | 1        |
+/// | c_0 * x + c_1        | x   | c_1 | 0      | 1        |
+/// | ...                  | ... | ... | ...    | ...      |
+/// | P(x)                 | x   | c_n | 0      | 0        |
+///
+/// Like the vertical-mul gate, everything happens inside the `acc` column; `x` is repeated on
+/// every row only because a gate can't reach a cell at a fixed distance from the top. The first
+/// `x` is a copy of the cell given to `evaluate`, so it can come from another chip or be public.
+#[derive(Clone, Debug)]
+pub struct HornerChip<F: Field> {
+    _ph: PhantomData<F>,
+    q_init: Selector,
+    q_horner: Selector,
+    q_root: Selector,
+    acc: Column<Advice>,
+    x: Column<Advice>,
+    coefficients: Column<Fixed>,
+    instance: Column<Instance>,
+}
+
+impl<F: Field> HornerChip<F> {
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        acc: Column<Advice>,
+        x: Column<Advice>,
+        coefficients: Column<Fixed>,
+        instance: Column<Instance>,
+    ) -> Self {
+        let q_init = meta.selector();
+        let q_horner = meta.selector();
+        let q_root = meta.selector();
+        meta.enable_equality(acc);
+        meta.enable_equality(x);
+        meta.enable_equality(instance);
+
+        // acc = c_0 on the first row
+        meta.create_gate("horner-init", |meta| {
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let c = meta.query_fixed(coefficients, Rotation::cur());
+            let q_init = meta.query_selector(q_init);
+            vec![q_init * (acc - c)]
+        });
+
+        // acc_next = acc * x + c_next, and x doesn't change from one row to the next
+        meta.create_gate("horner-step", |meta| {
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+            let x_cur = meta.query_advice(x, Rotation::cur());
+            let x_next = meta.query_advice(x, Rotation::next());
+            let c_next = meta.query_fixed(coefficients, Rotation::next());
+            let q_horner = meta.query_selector(q_horner);
+            vec![
+                q_horner.clone() * (acc_next - (acc_cur * x_cur.clone() + c_next)),
+                q_horner * (x_next - x_cur),
+            ]
+        });
+
+        // acc = 0
+        meta.create_gate("horner-root", |meta| {
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let q_root = meta.query_selector(q_root);
+            vec![q_root * acc]
+        });
+
+        Self { _ph: PhantomData, q_init, q_horner, q_root, acc, x, coefficients, instance }
+    }
+
+    pub fn load_private(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "private x",
+            |mut region| region.assign_advice(|| "x", self.x, 0, || value),
+        )
+    }
+
+    /// Returns the cell holding `P(x)`. Fails if there are no coefficients.
+    pub fn evaluate(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+        coefficients: &[F],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        if coefficients.is_empty() {
+            return Err(Error::Synthesis);
+        }
+
+        layouter.assign_region(
+            || "horner",
+            |mut region| {
+                let mut acc = Value::known(coefficients[0]);
+                let mut acc_cell = region.assign_advice(|| "acc", self.acc, 0, || acc)?;
+                // the gate keeps the rows below equal to this copy
+                let x = x.copy_advice(|| "x", &mut region, self.x, 0)?.value().cloned();
+                region.assign_fixed(|| "c", self.coefficients, 0, || Value::known(coefficients[0]))?;
+                self.q_init.enable(&mut region, 0)?;
+
+                for (i, c) in coefficients.iter().enumerate().skip(1) {
+                    self.q_horner.enable(&mut region, i - 1)?;
+                    acc = acc * x + Value::known(*c);
+                    acc_cell = region.assign_advice(|| "acc", self.acc, i, || acc)?;
+                    region.assign_advice(|| "x", self.x, i, || x)?;
+                    region.assign_fixed(|| "c", self.coefficients, i, || Value::known(*c))?;
+                }
+                Ok(acc_cell)
+            },
+        )
+    }
+
+    /// Constrains `y` to be 0.
+    pub fn constrain_root(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        y: &AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "root",
+            |mut region| {
+                y.copy_advice(|| "y", &mut region, self.acc, 0)?;
+                self.q_root.enable(&mut region, 0)
+            },
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.instance, row)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HornerMode {
+    /// `P(x)` is the first public input
+    Evaluate,
+    /// `P(x) = 0`, there are no public inputs
+    Root,
+}
+
+/// Proves knowledge of a secret `x` such that `P(x) = y` for a public `y`, or such that `x` is a
+/// root of `P`. The coefficients (highest degree first) are part of the circuit.
+pub struct HornerCircuit<F: Field> {
+    pub x: Value<F>,
+    pub coefficients: Vec<F>,
+    pub mode: HornerMode,
+}
+
+#[derive(Clone, Debug)]
+pub struct HornerConfig<F: Field + Clone> {
+    horner_chip: HornerChip<F>,
+}
+
+impl<F: Field> Circuit<F> for HornerCircuit<F> {
+    type Config = HornerConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        HornerCircuit {
+            x: Value::unknown(),
+            coefficients: self.coefficients.clone(),
+            mode: self.mode,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let acc = meta.advice_column();
+        let x = meta.advice_column();
+        let coefficients = meta.fixed_column();
+        let instance = meta.instance_column();
+        let horner_chip = HornerChip::configure(meta, acc, x, coefficients, instance);
+        HornerConfig { horner_chip }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = config.horner_chip;
+        let x = chip.load_private(&mut layouter, self.x)?;
+        let y = chip.evaluate(&mut layouter, &x, &self.coefficients)?;
+        match self.mode {
+            HornerMode::Evaluate => chip.expose_public(&mut layouter, &y, 0),
+            HornerMode::Root => chip.constrain_root(&mut layouter, &y),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+    use crate::tests::verify;
+
+    // P(x) = x^3 - 6x^2 + 11x - 6 = (x - 1)(x - 2)(x - 3)
+    fn cubic() -> Vec<Fr> {
+        vec![Fr::ONE, -Fr::from(6), Fr::from(11), -Fr::from(6)]
+    }
+
+    #[test]
+    fn test_should_evaluate_polynomial(){
+        // P(5) = 4 * 3 * 2
+        assert!(verify(&HornerCircuit { x: Value::known(Fr::from(5)), coefficients: cubic(), mode: HornerMode::Evaluate }, vec![Fr::from(24)]).is_ok());
+        assert!(verify(&HornerCircuit { x: Value::known(Fr::from(5)), coefficients: cubic(), mode: HornerMode::Evaluate }, vec![Fr::from(25)]).is_err());
+        // constant polynomial, a single row
+        assert!(verify(&HornerCircuit { x: Value::known(Fr::from(5)), coefficients: vec![Fr::from(7)], mode: HornerMode::Evaluate }, vec![Fr::from(7)]).is_ok());
+    }
+
+    #[test]
+    fn test_should_prove_knowledge_of_root(){
+        for root in [1, 2, 3] {
+            assert!(verify(&HornerCircuit { x: Value::known(Fr::from(root)), coefficients: cubic(), mode: HornerMode::Root }, vec![]).is_ok());
+        }
+        assert!(verify(&HornerCircuit { x: Value::known(Fr::from(4)), coefficients: cubic(), mode: HornerMode::Root }, vec![]).is_err());
+    }
+
+    #[test]
+    fn test_should_copy_x_from_another_cell(){
+        // x is the public input at row 1, P(x) the one at row 0
+        struct PublicX;
+
+        impl Circuit<Fr> for PublicX {
+            type Config = HornerConfig<Fr>;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                PublicX
+            }
+
+            fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+                HornerCircuit::configure(meta)
+            }
+
+            fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+                let chip = config.horner_chip;
+                let x = layouter.assign_region(
+                    || "public x",
+                    |mut region| region.assign_advice_from_instance(|| "x", chip.instance, 1, chip.x, 0),
+                )?;
+                let y = chip.evaluate(&mut layouter, &x, &cubic())?;
+                chip.expose_public(&mut layouter, &y, 0)
+            }
+        }
+
+        let prover = |public: Vec<u64>| MockProver::run(8, &PublicX, vec![public.into_iter().map(Fr::from).collect()]).unwrap();
+        assert!(prover(vec![24, 5]).verify().is_ok());
+        assert!(prover(vec![24, 6]).verify().is_err());
+    }
+
+    #[test]
+    fn test_should_reject_empty_polynomial(){
+        let circuit = HornerCircuit { x: Value::known(Fr::ONE), coefficients: vec![], mode: HornerMode::Root };
+        assert!(MockProver::run(8, &circuit, vec![vec![]]).is_err());
+    }
+}
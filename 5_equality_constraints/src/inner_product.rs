@@ -0,0 +1,329 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance, Selector},
+    poly::Rotation,
+};
+
+use ff::Field;
+
+/// Proves `acc = a_0 * b_0 + ... + a_n * b_n`. It generalizes the vertical-mul gate: the
+/// factors are side by side, and the product is added to a running accumulator:
+///
+/// | a   | b   | acc                       | q_first | q_step |
+/// | a_0 | b_0 | a_0 * b_0                 | 1       | 0      |
+/// | a_1 | b_1 | a_0 * b_0 + a_1 * b_1     | 0       | 1      |
+/// | ... | ... | ...                       | ...     | ...    |
+///
+/// The operands are copied in, so they can come from anywhere in the circuit (private values,
+/// public inputs, the output of another chip) and can be reused by several inner products.
+#[derive(Clone, Debug)]
+pub struct InnerProductChip<F: Field> {
+    _ph: PhantomData<F>,
+    q_first: Selector,
+    q_step: Selector,
+    a: Column<Advice>,
+    b: Column<Advice>,
+    acc: Column<Advice>,
+    instance: Column<Instance>,
+}
+
+impl<F: Field> InnerProductChip<F> {
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        a: Column<Advice>,
+        b: Column<Advice>,
+        acc: Column<Advice>,
+        instance: Column<Instance>,
+    ) -> Self {
+        let q_first = meta.selector();
+        let q_step = meta.selector();
+        meta.enable_equality(a);
+        meta.enable_equality(b);
+        meta.enable_equality(acc);
+        meta.enable_equality(instance);
+
+        meta.create_gate("inner-product-first", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let q_first = meta.query_selector(q_first);
+            vec![q_first * (acc - a * b)]
+        });
+
+        meta.create_gate("inner-product-step", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let q_step = meta.query_selector(q_step);
+            vec![q_step * (acc - (acc_prev + a * b))]
+        });
+
+        Self { _ph: PhantomData, q_first, q_step, a, b, acc, instance }
+    }
+
+    pub fn load_private(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        values: &[Value<F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        layouter.assign_region(
+            || "private operands",
+            |mut region| {
+                values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| region.assign_advice(|| "private", self.a, i, || *value))
+                    .collect()
+            },
+        )
+    }
+
+    /// Loads `len` public inputs, starting at row `start` of the instance column.
+    pub fn load_public(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        start: usize,
+        len: usize,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        layouter.assign_region(
+            || "public operands",
+            |mut region| {
+                (0..len)
+                    .map(|i| region.assign_advice_from_instance(|| "public", self.instance, start + i, self.a, i))
+                    .collect()
+            },
+        )
+    }
+
+    /// Fails if the vectors are empty or have different lengths.
+    pub fn inner_product(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        a: &[AssignedCell<F, F>],
+        b: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        if a.is_empty() || a.len() != b.len() {
+            return Err(Error::Synthesis);
+        }
+
+        layouter.assign_region(
+            || "inner product",
+            |mut region| {
+                let mut acc = Value::known(F::ZERO);
+                let mut acc_cell = None;
+                for (i, (a, b)) in a.iter().zip(b).enumerate() {
+                    let a = a.copy_advice(|| "a", &mut region, self.a, i)?;
+                    let b = b.copy_advice(|| "b", &mut region, self.b, i)?;
+                    if i == 0 {
+                        self.q_first.enable(&mut region, i)?;
+                    } else {
+                        self.q_step.enable(&mut region, i)?;
+                    }
+
+                    acc = acc + a.value().copied() * b.value().copied();
+                    acc_cell = Some(region.assign_advice(|| "acc", self.acc, i, || acc)?);
+                }
+                Ok(acc_cell.unwrap())
+            },
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.instance, row)
+    }
+}
+
+/// Matrices are `Vec`s of rows.
+pub type Matrix<T> = Vec<Vec<T>>;
+
+/// `C = A * B`: every entry of `C` is the inner product of a row of `A` and a column of `B`.
+/// Every entry of `A` is copied into `B.cols` inner products, and every entry of `B` into
+/// `A.rows` of them.
+#[derive(Clone, Debug)]
+pub struct MatMulChip<F: Field> {
+    inner_product: InnerProductChip<F>,
+}
+
+impl<F: Field> MatMulChip<F> {
+    pub fn new(inner_product: InnerProductChip<F>) -> Self {
+        Self { inner_product }
+    }
+
+    pub fn inner_product_chip(&self) -> &InnerProductChip<F> {
+        &self.inner_product
+    }
+
+    /// Fails if the number of columns of `a` isn't the number of rows of `b`.
+    pub fn matmul(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        a: &Matrix<AssignedCell<F, F>>,
+        b: &Matrix<AssignedCell<F, F>>,
+    ) -> Result<Matrix<AssignedCell<F, F>>, Error> {
+        let inner = b.len();
+        if a.iter().any(|row| row.len() != inner) {
+            return Err(Error::Synthesis);
+        }
+        let cols = b.first().map(|row| row.len()).unwrap_or(0);
+        if b.iter().any(|row| row.len() != cols) {
+            return Err(Error::Synthesis);
+        }
+
+        let b_cols: Matrix<AssignedCell<F, F>> =
+            (0..cols).map(|j| b.iter().map(|row| row[j].clone()).collect()).collect();
+
+        a.iter()
+            .map(|row| {
+                b_cols
+                    .iter()
+                    .map(|col| self.inner_product.inner_product(layouter, row, col))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Proves `C = A * B` for a private `A`, where `C` is public. `B` is private too, unless
+/// `public_b` is set: then its values are ignored (only its shape matters) and it is read from
+/// the instance column instead.
+///
+/// Instance column layout: `B` row by row if it is public, followed by `C` row by row.
+pub struct MatMulCircuit<F: Field> {
+    pub a: Matrix<Value<F>>,
+    pub b: Matrix<Value<F>>,
+    pub public_b: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct MatMulConfig<F: Field + Clone> {
+    matmul_chip: MatMulChip<F>,
+}
+
+fn unknown<F: Field>(matrix: &Matrix<Value<F>>) -> Matrix<Value<F>> {
+    matrix.iter().map(|row| vec![Value::unknown(); row.len()]).collect()
+}
+
+impl<F: Field> Circuit<F> for MatMulCircuit<F> {
+    type Config = MatMulConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        MatMulCircuit {
+            // the shapes are part of the circuit, only the values are witnesses
+            a: unknown(&self.a),
+            b: unknown(&self.b),
+            public_b: self.public_b,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let acc = meta.advice_column();
+        let instance = meta.instance_column();
+        let inner_product = InnerProductChip::configure(meta, a, b, acc, instance);
+        MatMulConfig { matmul_chip: MatMulChip::new(inner_product) }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = config.matmul_chip;
+        let ip = chip.inner_product_chip();
+
+        let a = self
+            .a
+            .iter()
+            .map(|row| ip.load_private(&mut layouter, row))
+            .collect::<Result<Matrix<_>, _>>()?;
+
+        let mut instance_row = 0;
+        let b = self
+            .b
+            .iter()
+            .map(|row| {
+                if self.public_b {
+                    let cells = ip.load_public(&mut layouter, instance_row, row.len());
+                    instance_row += row.len();
+                    cells
+                } else {
+                    ip.load_private(&mut layouter, row)
+                }
+            })
+            .collect::<Result<Matrix<_>, _>>()?;
+
+        let c = chip.matmul(&mut layouter, &a, &b)?;
+        for cell in c.iter().flatten() {
+            ip.expose_public(&mut layouter, cell, instance_row)?;
+            instance_row += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+
+    fn matrix(rows: &[&[u64]]) -> Matrix<Value<Fr>> {
+        rows.iter().map(|row| row.iter().map(|x| Value::known(Fr::from(*x))).collect()).collect()
+    }
+
+    fn public(values: &[u64]) -> Vec<Fr> {
+        values.iter().map(|x| Fr::from(*x)).collect()
+    }
+
+    #[test]
+    fn test_should_compute_inner_product(){
+        // a row vector times a column vector
+        let circuit = MatMulCircuit { a: matrix(&[&[1, 2, 3]]), b: matrix(&[&[4], &[5], &[6]]), public_b: false };
+        let prover = MockProver::run(8, &circuit, vec![public(&[32])]).unwrap();
+        prover.verify().unwrap();
+
+        let prover = MockProver::run(8, &circuit, vec![public(&[33])]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_should_multiply_private_matrices(){
+        // [1 2] [5 6]   [19 22]
+        // [3 4] [7 8] = [43 50]
+        let circuit = MatMulCircuit { a: matrix(&[&[1, 2], &[3, 4]]), b: matrix(&[&[5, 6], &[7, 8]]), public_b: false };
+        let prover = MockProver::run(8, &circuit, vec![public(&[19, 22, 43, 50])]).unwrap();
+        prover.verify().unwrap();
+
+        let prover = MockProver::run(8, &circuit, vec![public(&[19, 22, 50, 43])]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_should_multiply_by_public_matrix(){
+        // the values of b are ignored, they come from the instance column
+        let circuit = MatMulCircuit { a: matrix(&[&[1, 2], &[3, 4]]), b: matrix(&[&[0, 0], &[0, 0]]), public_b: true };
+        let prover = MockProver::run(8, &circuit, vec![public(&[5, 6, 7, 8, 19, 22, 43, 50])]).unwrap();
+        prover.verify().unwrap();
+
+        // another public b gives another product
+        let prover = MockProver::run(8, &circuit, vec![public(&[5, 6, 7, 9, 19, 22, 43, 50])]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_should_reject_mismatched_shapes(){
+        let circuit = MatMulCircuit { a: matrix(&[&[1, 2]]), b: matrix(&[&[5, 6]]), public_b: false };
+        assert!(MockProver::run(8, &circuit, vec![public(&[])]).is_err());
+    }
+}
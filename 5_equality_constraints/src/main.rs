@@ -1,71 +1,147 @@
+mod addition_chain;
+mod exponentiation;
+mod horner;
+mod inner_product;
+
+use std::cell::Cell;
 use std::marker::PhantomData;
 
 use halo2_proofs::{
     circuit::{Layouter, SimpleFloorPlanner, Value},
     dev::MockProver,
-    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Selector},
-    poly::Rotation,
+    plonk::{Circuit, ConstraintSystem, Error},
 };
 
 use ff::Field;
 use halo2_proofs::circuit::AssignedCell;
 
+use arithmetic::{ArithmeticChip, ArithmeticConfig, ArithmeticInstructions};
+
+use addition_chain::AdditionChain;
+use exponentiation::ExpCircuit;
+use horner::{HornerCircuit, HornerMode};
+use inner_product::MatMulCircuit;
+
+// public exponent of both `TestCircuit` and `ExpCircuit`
+const EXPONENT: u64 = 5;
+
+/// How the multiplications of an addition chain are laid out in the advice column.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MulLayout {
+    /// One 3-row `mul` region per multiplication, both factors copied in
+    Separate,
+    /// A single region where the product of a multiplication is the first factor of the next
+    /// one, so only the second factor has to be copied in
+    Chained,
+}
+
+/// Proves `a^n` for a private `a`, following `chain`. `a^n` is the first public input.
 struct TestCircuit<F: Field> {
     _ph: PhantomData<F>,
     secret: Value<F>,
+    chain: AdditionChain,
+    layout: MulLayout,
+    /// rows used by the regions, counting the row of `a`; set by `synthesize`
+    rows: Cell<usize>,
+}
+
+/// Rows of the region holding `cell`, if it is the last one of its region.
+fn region_rows<F: Field>(cell: &AssignedCell<F, F>) -> usize {
+    cell.cell().row_offset + 1
 }
 
 #[derive(Clone, Debug)]
 struct TestConfig<F: Field + Clone> {
     _ph: PhantomData<F>,
-    q_mul: Selector,
-    advice: Column<Advice>,
+    arithmetic: ArithmeticConfig,
 }
 
 impl<F: Field> TestCircuit<F> {
-    fn mul(
-        config: &<Self as Circuit<F>>::Config,
+    /// Follows the addition chain from `base`, one `mul` region per step. Every element of the
+    /// chain is kept, since any of them can be a factor of a later step. Also returns the rows
+    /// of the `mul` regions.
+    fn pow(
+        chip: &ArithmeticChip<F>,
         layouter: &mut impl Layouter<F>,
-        lhs: AssignedCell<F, F>,
-        rhs: AssignedCell<F, F>,
-    ) -> Result<AssignedCell<F, F>, Error> {
-        layouter.assign_region(
-            || "mul",
-            |mut region| {
-
-
-                let w0: AssignedCell<F, F> = lhs.copy_advice(|| "assign w0", &mut region, config.advice, 0)?;
-                let w1 = rhs.copy_advice(|| "assign w1", &mut region, config.advice, 1)?;
-
-                let w2 = w0.value().and_then(|w0| w1.value().and_then(|w1| Value::known((*w0) * (*w1))));
-                let w2 = region.assign_advice(|| "assign w2", config.advice, 2, || w2)?;
-                config.q_mul.enable(&mut region, 0)?;
-                Ok(w2)
-            },
-        )
+        base: AssignedCell<F, F>,
+        chain: &AdditionChain,
+    ) -> Result<(AssignedCell<F, F>, usize), Error> {
+        let mut powers = vec![base];
+        let mut rows = 0;
+        for (i, j) in chain.steps() {
+            let next = chip.mul(layouter, &powers[*i], &powers[*j])?;
+            rows += region_rows(&next);
+            powers.push(next);
+        }
+        Ok((powers.pop().unwrap(), rows))
     }
 
-    /// This region occupies 1 row. Esta es una función auxiliar para crear una región unitaria
-    /// (una única fila)
-    fn unconstrained(
+    /// Same as `pow`, but in a single region that overlaps the multiplications:
+    ///
+    /// | advice | q_mul |
+    /// | a      | 1     |
+    /// | a      | 0     |  <- copy
+    /// | a^2    | 1     |
+    /// | a^2    | 0     |  <- copy
+    /// | a^4    | 1     |
+    /// | a      | 0     |  <- copy
+    /// | a^5    | 0     |
+    ///
+    /// Each product row is also the first row of the next vertical-mul, so a multiplication takes
+    /// 2 rows instead of 3. The secret is assigned on the first row instead of in its own region.
+    fn pow_chained(
         config: &<Self as Circuit<F>>::Config,
         layouter: &mut impl Layouter<F>,
-        value: Value<F>,
+        secret: Value<F>,
+        chain: &AdditionChain,
     ) -> Result<AssignedCell<F, F>, Error> {
+        let ArithmeticConfig { advice, q_mul, .. } = config.arithmetic;
         layouter.assign_region(
-            || "free variable",
+            || "mul chain",
             |mut region| {
-                region.assign_advice(
-                    || "assign w0",
-                    config.advice,
-                    0,
-                    || value,
-                )
+                let mut powers = vec![region.assign_advice(|| "assign a", advice, 0, || secret)?];
+                // row of the last element of `powers`
+                let mut row = 0;
+                for (k, (i, j)) in chain.steps().iter().enumerate() {
+                    let (w0, rhs) = if *i == k {
+                        (powers[k].clone(), *j)
+                    } else if *j == k {
+                        (powers[k].clone(), *i)
+                    } else {
+                        // neither factor is the previous product, start a new vertical-mul below it
+                        row += 1;
+                        (powers[*i].copy_advice(|| "assign w0", &mut region, advice, row)?, *j)
+                    };
+                    let w1 = powers[rhs].copy_advice(|| "assign w1", &mut region, advice, row + 1)?;
+
+                    let w2 = w0.value().and_then(|w0| w1.value().and_then(|w1| Value::known((*w0) * (*w1))));
+                    let w2 = region.assign_advice(|| "assign w2", advice, row + 2, || w2)?;
+                    q_mul.enable(&mut region, row)?;
+                    powers.push(w2);
+                    row += 2;
+                }
+                Ok(powers.pop().unwrap())
             },
         )
     }
 }
 
+impl<F: Field + From<u64>> TestCircuit<F> {
+    fn new(secret: u64, chain: AdditionChain, layout: MulLayout) -> Self {
+        TestCircuit { _ph: PhantomData, secret: Value::known(F::from(secret)), chain, layout, rows: Cell::new(0) }
+    }
+}
+
+/// Rows laid out to compute `2^n` with `chain`, `n` being its target.
+fn measure(chain: &AdditionChain, layout: MulLayout) -> usize {
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    let circuit = TestCircuit::<Fr>::new(2, chain.clone(), layout);
+    let public = Fr::from(2).pow_vartime([chain.target()]);
+    MockProver::run(8, &circuit, vec![vec![public]]).unwrap().verify().unwrap();
+    circuit.rows.get()
+}
+
 impl<F: Field> Circuit<F> for TestCircuit<F> {
     type Config = TestConfig<F>;
     type FloorPlanner = SimpleFloorPlanner;
@@ -74,27 +150,22 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
         TestCircuit {
             _ph: PhantomData,
             secret: Value::unknown(),
+            chain: self.chain.clone(),
+            layout: self.layout,
+            rows: Cell::new(0),
         }
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        let q_enable = meta.complex_selector();
         let advice = meta.advice_column();
-        meta.enable_equality(advice);
+        let instance = meta.instance_column();
 
-        // define a new gate:
-        meta.create_gate("vertical-mul", |meta| {
-            let w0 = meta.query_advice(advice, Rotation(0));
-            let w1 = meta.query_advice(advice, Rotation(1));
-            let w3 = meta.query_advice(advice, Rotation(2));
-            let q_enable = meta.query_selector(q_enable);
-            vec![q_enable * (w0 * w1 - w3)]
-        });
+        // the "vertical-mul" gate lives in the chip
+        let arithmetic = ArithmeticChip::configure(meta, advice, Some(instance));
 
         TestConfig {
             _ph: PhantomData,
-            q_mul: q_enable,
-            advice,
+            arithmetic,
         }
     }
 
@@ -105,44 +176,146 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
         config: Self::Config, //
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
-        // create a new free variable
-        let a = TestCircuit::<F>::unconstrained(
-            &config,
-            &mut layouter,
-            self.secret.clone(),
-        )?;
-
-        // do a few multiplications
-        let a2 = TestCircuit::<F>::mul(
-            &config, //
-            &mut layouter,
-            a.clone(),
-            a.clone(),
-        )?;
-        let a3 = TestCircuit::<F>::mul(
-            &config, //
-            &mut layouter,
-            a2.clone(),
-            a.clone(),
-        )?;
-        let _a5 = TestCircuit::<F>::mul(
-            &config, //
-            &mut layouter,
-            a3.clone(),
-            a2.clone(),
-        )?;
-
-        Ok(())
+        // do a few multiplications: for a^5 the chain is a, a^2, a^3 (or a^4), a^5
+        let chip = ArithmeticChip::construct(config.arithmetic.clone());
+        let power = match self.layout {
+            MulLayout::Separate => {
+                // create a new free variable
+                let a = chip.load_private(&mut layouter, self.secret)?;
+                let a_rows = region_rows(&a);
+                let (power, mul_rows) = TestCircuit::<F>::pow(&chip, &mut layouter, a, &self.chain)?;
+                self.rows.set(a_rows + mul_rows);
+                power
+            }
+            MulLayout::Chained => {
+                let power = TestCircuit::<F>::pow_chained(&config, &mut layouter, self.secret, &self.chain)?;
+                self.rows.set(region_rows(&power));
+                power
+            }
+        };
+
+        chip.expose_public(&mut layouter, &power, 0)
     }
 }
 
 fn main() {
     use halo2_proofs::halo2curves::bn256::Fr;
 
-    let circuit = TestCircuit::<Fr> {
-        _ph: PhantomData,
+    for layout in [MulLayout::Separate, MulLayout::Chained] {
+        let circuit = TestCircuit::<Fr>::new(3, AdditionChain::shortest(EXPONENT), layout);
+        let prover = MockProver::run(8, &circuit, vec![vec![Fr::from(243)]]).unwrap();
+        prover.verify().unwrap();
+    }
+
+    for exponent in [EXPONENT, 15, 23, 127] {
+        println!(
+            "a^{}: {} multiplications, {} saved against square-and-multiply",
+            exponent,
+            AdditionChain::shortest(exponent).len(),
+            AdditionChain::saved_multiplications(exponent),
+        );
+    }
+
+    println!("rows used by a^n:");
+    println!("| n   | separate | chained |");
+    for exponent in [EXPONENT, 15, 23, 127] {
+        let chain = AdditionChain::shortest(exponent);
+        println!(
+            "| {:<3} | {:<8} | {:<7} |",
+            exponent,
+            measure(&chain, MulLayout::Separate),
+            measure(&chain, MulLayout::Chained),
+        );
+    }
+    // 1, 2, 3, 4, 7 isn't the shortest chain for 7, and 4 = 2 + 2 doesn't use the previous
+    // product, so the chained layout copies a^2 again
+    let chain = AdditionChain::from_steps(&[(0, 0), (1, 0), (1, 1), (2, 3)]).unwrap();
+    println!(
+        "| {:<3} | {:<8} | {:<7} | (1, 2, 3, 4, 7)",
+        chain.target(),
+        measure(&chain, MulLayout::Separate),
+        measure(&chain, MulLayout::Chained),
+    );
+
+    // same a^5, built by the exponentiation chip and exposed as a public input
+    let circuit = ExpCircuit::<Fr> {
         secret: Value::known(Fr::from(3)),
+        exponent: EXPONENT,
+    };
+    let prover = MockProver::run(8, &circuit, vec![vec![Fr::from(243)]]).unwrap();
+    prover.verify().unwrap();
+
+    // P(x) = x^2 - 5x + 6 = (x - 2)(x - 3): P(4) = 2 is public, and 3 is a root
+    let coefficients = vec![Fr::ONE, -Fr::from(5), Fr::from(6)];
+    let circuit = HornerCircuit::<Fr> {
+        x: Value::known(Fr::from(4)),
+        coefficients: coefficients.clone(),
+        mode: HornerMode::Evaluate,
     };
-    let prover = MockProver::run(8, &circuit, vec![]).unwrap();
+    let prover = MockProver::run(8, &circuit, vec![vec![Fr::from(2)]]).unwrap();
     prover.verify().unwrap();
+
+    let circuit = HornerCircuit::<Fr> {
+        x: Value::known(Fr::from(3)),
+        coefficients,
+        mode: HornerMode::Root,
+    };
+    let prover = MockProver::run(8, &circuit, vec![vec![]]).unwrap();
+    prover.verify().unwrap();
+
+    // [1 2] [5 6]   [19 22]
+    // [3 4] [7 8] = [43 50], with a private and b public
+    let known = |rows: [[u64; 2]; 2]| rows.iter().map(|row| row.iter().map(|x| Value::known(Fr::from(*x))).collect()).collect();
+    let circuit = MatMulCircuit::<Fr> {
+        a: known([[1, 2], [3, 4]]),
+        b: known([[5, 6], [7, 8]]),
+        public_b: true,
+    };
+    let public = [5, 6, 7, 8, 19, 22, 43, 50].map(Fr::from).to_vec();
+    let prover = MockProver::run(8, &circuit, vec![public]).unwrap();
+    prover.verify().unwrap();
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use halo2_proofs::dev::VerifyFailure;
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+
+    /// Runs `circuit` with `public` as its instance column, for the tests of every module.
+    pub(crate) fn verify(circuit: &impl Circuit<Fr>, public: Vec<Fr>) -> Result<(), Vec<VerifyFailure>> {
+        MockProver::run(8, circuit, vec![public]).unwrap().verify()
+    }
+
+    #[test]
+    fn test_layouts_should_agree(){
+        for layout in [MulLayout::Separate, MulLayout::Chained] {
+            assert!(verify(&TestCircuit::new(3, AdditionChain::shortest(EXPONENT), layout), vec![Fr::from(243)]).is_ok());
+            assert!(verify(&TestCircuit::new(3, AdditionChain::shortest(EXPONENT), layout), vec![Fr::from(244)]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_chained_layout_uses_fewer_rows(){
+        for n in [2, EXPONENT, 15, 23, 127] {
+            let chain = AdditionChain::shortest(n);
+            assert_eq!(measure(&chain, MulLayout::Separate), 1 + 3 * chain.len());
+            // shortest chains always reuse the previous product
+            assert_eq!(measure(&chain, MulLayout::Chained), 1 + 2 * chain.len());
+        }
+    }
+
+    #[test]
+    fn test_chained_layout_restarts_when_the_previous_product_is_not_a_factor(){
+        // 1, 2, 3, 4, 7: 4 = 2 + 2 doesn't use 3, the previous element
+        let chain = AdditionChain::from_steps(&[(0, 0), (1, 0), (1, 1), (2, 3)]).unwrap();
+        for layout in [MulLayout::Separate, MulLayout::Chained] {
+            assert!(verify(&TestCircuit::new(3, chain.clone(), layout), vec![Fr::from(3u64.pow(7))]).is_ok());
+            assert!(verify(&TestCircuit::new(3, chain.clone(), layout), vec![Fr::from(3u64.pow(7) + 1)]).is_err());
+        }
+        assert_eq!(measure(&chain, MulLayout::Separate), 13);
+        // one extra row to copy a^2 below a^3
+        assert_eq!(measure(&chain, MulLayout::Chained), 10);
+    }
 }
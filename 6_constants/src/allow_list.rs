@@ -0,0 +1,239 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Selector, TableColumn},
+    poly::Rotation,
+};
+
+use ff::PrimeField;
+
+#[derive(Debug)]
+pub enum AllowListError {
+    Io(io::Error),
+    /// `line` is 1-based
+    InvalidEntry { line: usize, entry: String },
+    Empty,
+}
+
+impl fmt::Display for AllowListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AllowListError::Io(e) => write!(f, "could not read the allow list: {}", e),
+            AllowListError::InvalidEntry { line, entry } => write!(f, "line {}: invalid entry {:?}", line, entry),
+            AllowListError::Empty => write!(f, "the allow list is empty"),
+        }
+    }
+}
+
+/// Parses one entry per line, see `encode`. Blank lines and lines starting with `#` are skipped.
+pub fn parse_allow_list<F: PrimeField>(source: &str) -> Result<Vec<F>, AllowListError> {
+    let mut entries = vec![];
+    for (i, line) in source.lines().enumerate() {
+        let entry = line.trim();
+        if entry.is_empty() || entry.starts_with('#') {
+            continue;
+        }
+        let value = encode(entry).ok_or_else(|| AllowListError::InvalidEntry {
+            line: i + 1,
+            entry: entry.to_string(),
+        })?;
+        entries.push(F::from(value));
+    }
+    if entries.is_empty() {
+        return Err(AllowListError::Empty);
+    }
+    Ok(entries)
+}
+
+pub fn load_allow_list<F: PrimeField>(path: impl AsRef<Path>) -> Result<Vec<F>, AllowListError> {
+    let source = fs::read_to_string(path).map_err(AllowListError::Io)?;
+    parse_allow_list(&source)
+}
+
+/// Set in every code and in no ID. ASCII never uses the top bit of a byte, so a code never
+/// sets it by itself.
+const CODE_TAG: u64 = 1 << 63;
+
+/// An entry is either a decimal number below `2^63` (an ID) or up to 8 ASCII letters and digits
+/// (a code such as `AR`), read as a big-endian integer and tagged with `CODE_TAG`, so an ID and
+/// a code are never the same number.
+pub fn encode(entry: &str) -> Option<u64> {
+    if entry.bytes().all(|b| b.is_ascii_digit()) {
+        return entry.parse().ok().filter(|id| id & CODE_TAG == 0);
+    }
+    if entry.len() > 8 || !entry.bytes().all(|b| b.is_ascii_alphanumeric()) {
+        return None;
+    }
+    Some(entry.bytes().fold(0, |acc, b| (acc << 8) | b as u64) | CODE_TAG)
+}
+
+/// Proves that a secret is one of the constants of a table:
+///
+/// | value | q_member |    | tag | entry |
+/// | x     | 1        |    | 0   | 0     |
+///                           | 1   | e_0   |
+///                           | 1   | e_1   |
+///                           | ... | ...   |
+///
+/// The lookup is `(q_member, q_member * x) in (tag, entry)`. Rows where `q_member` is 0 look up
+/// `(0, 0)`, which is why it is in the table, and the tag keeps a secret 0 from matching that
+/// row when it is not in the list.
+///
+/// A table uses one row per entry, so with `2^k` rows the list can hold a bit less than `2^k`
+/// entries (the last rows are reserved for blinding).
+#[derive(Clone, Debug)]
+pub struct AllowListConfig {
+    q_member: Selector,
+    value: Column<Advice>,
+    tag: TableColumn,
+    entry: TableColumn,
+}
+
+impl AllowListConfig {
+    pub fn configure<F: PrimeField>(meta: &mut ConstraintSystem<F>, value: Column<Advice>) -> Self {
+        let q_member = meta.complex_selector();
+        let tag = meta.lookup_table_column();
+        let entry = meta.lookup_table_column();
+        meta.enable_equality(value);
+
+        meta.lookup("allow-list", |meta| {
+            let x = meta.query_advice(value, Rotation::cur());
+            let q_member = meta.query_selector(q_member);
+            vec![(q_member.clone(), tag), (q_member * x, entry)]
+        });
+
+        Self { q_member, value, tag, entry }
+    }
+
+    pub fn load_table<F: PrimeField>(&self, layouter: &mut impl Layouter<F>, entries: &[F]) -> Result<(), Error> {
+        layouter.assign_table(
+            || "allow list",
+            |mut table| {
+                table.assign_cell(|| "tag", self.tag, 0, || Value::known(F::ZERO))?;
+                table.assign_cell(|| "entry", self.entry, 0, || Value::known(F::ZERO))?;
+                for (i, entry) in entries.iter().enumerate() {
+                    table.assign_cell(|| "tag", self.tag, i + 1, || Value::known(F::ONE))?;
+                    table.assign_cell(|| "entry", self.entry, i + 1, || Value::known(*entry))?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    pub fn load_private<F: PrimeField>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "private value",
+            |mut region| region.assign_advice(|| "value", self.value, 0, || value),
+        )
+    }
+
+    /// Looks up a copy of `value`, so the member is the same cell the rest of the circuit uses.
+    pub fn assert_member<F: PrimeField>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "member",
+            |mut region| {
+                self.q_member.enable(&mut region, 0)?;
+                value.copy_advice(|| "value", &mut region, self.value, 0)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+/// The allow list is part of the circuit (it's in the table), so it is public; the secret
+/// stays private.
+pub struct AllowListCircuit<F: PrimeField> {
+    pub allow_list: Vec<F>,
+    pub secret: Value<F>,
+}
+
+impl<F: PrimeField> Circuit<F> for AllowListCircuit<F> {
+    type Config = AllowListConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        AllowListCircuit {
+            allow_list: self.allow_list.clone(),
+            secret: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let value = meta.advice_column();
+        AllowListConfig::configure(meta, value)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        config.load_table(&mut layouter, &self.allow_list)?;
+        let secret = config.load_private(&mut layouter, self.secret)?;
+        config.assert_member(&mut layouter, &secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+    use crate::tests::verify;
+
+    const COUNTRIES: &str = "
+        # Mercosur
+        AR
+        BR
+        PY
+        UY
+    ";
+
+    #[test]
+    fn test_should_parse_ids_and_codes(){
+        let entries = parse_allow_list::<Fr>("42\n\n# comment\nAR\n").unwrap();
+        assert_eq!(entries, vec![Fr::from(42), Fr::from(CODE_TAG | 0x4152)]);
+
+        assert!(matches!(
+            parse_allow_list::<Fr>("AR\nnot a code\n"),
+            Err(AllowListError::InvalidEntry { line: 2, .. })
+        ));
+        assert!(matches!(parse_allow_list::<Fr>("# nothing\n"), Err(AllowListError::Empty)));
+    }
+
+    #[test]
+    fn test_should_not_mix_ids_and_codes(){
+        // "AR" is 0x4152 without the tag
+        assert_eq!(encode("16722"), Some(16722));
+        assert_ne!(encode("16722"), encode("AR"));
+        // an ID can't reach the tag
+        assert_eq!(encode(&(CODE_TAG - 1).to_string()), Some(CODE_TAG - 1));
+        assert_eq!(encode(&(CODE_TAG | 0x4152).to_string()), None);
+        assert!(verify(&AllowListCircuit { allow_list: parse_allow_list("16722\n").unwrap(), secret: Value::known(Fr::from(encode("AR").unwrap())) }).is_err());
+        assert!(verify(&AllowListCircuit { allow_list: parse_allow_list("AR\n").unwrap(), secret: Value::known(Fr::from(16722)) }).is_err());
+    }
+
+    #[test]
+    fn test_should_accept_members(){
+        let countries = parse_allow_list::<Fr>(COUNTRIES).unwrap();
+        for code in ["AR", "BR", "PY", "UY"] {
+            assert!(verify(&AllowListCircuit { allow_list: countries.clone(), secret: Value::known(Fr::from(encode(code).unwrap())) }).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_should_not_accept_non_members(){
+        let countries = parse_allow_list::<Fr>(COUNTRIES).unwrap();
+        assert!(verify(&AllowListCircuit { allow_list: countries.clone(), secret: Value::known(Fr::from(encode("CL").unwrap())) }).is_err());
+        // (0, 0) is in the table, but with a tag of 0
+        assert!(verify(&AllowListCircuit { allow_list: countries, secret: Value::known(Fr::from(0)) }).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter},
+    plonk::{Advice, Column, ConstraintSystem, Error, Fixed},
+};
+
+use ff::{Field, PrimeField};
+
+use arithmetic::ArithmeticInstructions;
+
+#[derive(Clone, Debug)]
+pub struct ConstantsConfig {
+    advice: Column<Advice>,
+}
+
+impl ConstantsConfig {
+    /// `constants` becomes the column where the layouter places every constant of the circuit,
+    /// so no gate is needed: each advice cell is tied to its constant by a copy constraint.
+    pub fn configure<F: Field>(
+        meta: &mut ConstraintSystem<F>,
+        advice: Column<Advice>,
+        constants: Column<Fixed>,
+    ) -> Self {
+        meta.enable_equality(advice);
+        meta.enable_constant(constants);
+        Self { advice }
+    }
+}
+
+/// Hands out advice cells fixed to a constant. Every constant is assigned once: asking for it
+/// again returns the same cell, which chips can `copy_advice` from.
+///
+/// The cache is filled after `assign_region` returns, since the floor planner may run a region
+/// closure more than once.
+#[derive(Debug)]
+pub struct Constants<F: PrimeField> {
+    config: ConstantsConfig,
+    // keyed by the canonical representation, field elements aren't `Hash`
+    cache: HashMap<Vec<u8>, AssignedCell<F, F>>,
+}
+
+impl<F: PrimeField> Constants<F> {
+    pub fn new(config: ConstantsConfig) -> Self {
+        Self { config, cache: HashMap::new() }
+    }
+
+    pub fn get(&mut self, layouter: &mut impl Layouter<F>, value: F) -> Result<AssignedCell<F, F>, Error> {
+        let key = value.to_repr().as_ref().to_vec();
+        if let Some(cell) = self.cache.get(&key) {
+            return Ok(cell.clone());
+        }
+
+        let cell = layouter.assign_region(
+            || "constant",
+            |mut region| region.assign_advice_from_constant(|| "constant", self.config.advice, 0, value),
+        )?;
+        self.cache.insert(key, cell.clone());
+        Ok(cell)
+    }
+}
+
+/// Instructions that need a constant take `Constants` instead of assigning it themselves, so the
+/// whole circuit shares one cell per constant.
+pub trait ConstantInstructions<F: PrimeField>: ArithmeticInstructions<F, Num = AssignedCell<F, F>> {
+    /// `num == value`
+    fn constrain_constant(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        constants: &mut Constants<F>,
+        num: &Self::Num,
+        value: F,
+    ) -> Result<(), Error> {
+        let constant = constants.get(layouter, value)?;
+        self.constrain_equal(layouter, num, &constant)
+    }
+}
+
+impl<F: PrimeField, C: ArithmeticInstructions<F, Num = AssignedCell<F, F>>> ConstantInstructions<F> for C {}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        dev::MockProver,
+        halo2curves::bn256::Fr,
+        plonk::Circuit,
+    };
+
+    use super::*;
+
+    /// Proves `a == 5` twice and `b == 7`, and checks that 5 is assigned only once.
+    struct RepeatedConstantsCircuit {
+        a: Value<Fr>,
+        b: Value<Fr>,
+    }
+
+    impl Circuit<Fr> for RepeatedConstantsCircuit {
+        type Config = ConstantsConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            RepeatedConstantsCircuit { a: Value::unknown(), b: Value::unknown() }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let advice = meta.advice_column();
+            let constants = meta.fixed_column();
+            ConstantsConfig::configure(meta, advice, constants)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+            let advice = config.advice;
+            let mut constants = Constants::new(config);
+
+            let five = constants.get(&mut layouter, Fr::from(5))?;
+            let five_again = constants.get(&mut layouter, Fr::from(5))?;
+            let seven = constants.get(&mut layouter, Fr::from(7))?;
+            assert_eq!(five.cell(), five_again.cell());
+            assert_ne!(five.cell(), seven.cell());
+
+            layouter.assign_region(
+                || "private values",
+                |mut region| {
+                    let a = region.assign_advice(|| "a", advice, 0, || self.a)?;
+                    let b = region.assign_advice(|| "b", advice, 1, || self.b)?;
+                    region.constrain_equal(a.cell(), five.cell())?;
+                    region.constrain_equal(a.cell(), five_again.cell())?;
+                    region.constrain_equal(b.cell(), seven.cell())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn test_should_dedupe_constants(){
+        let circuit = RepeatedConstantsCircuit { a: Value::known(Fr::from(5)), b: Value::known(Fr::from(7)) };
+        let prover = MockProver::run(8, &circuit, vec![]).unwrap();
+        prover.verify().unwrap();
+    }
+
+    #[test]
+    fn test_should_not_accept_other_value(){
+        let circuit = RepeatedConstantsCircuit { a: Value::known(Fr::from(5)), b: Value::known(Fr::from(8)) };
+        let prover = MockProver::run(8, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -1,15 +1,20 @@
+mod allow_list;
+mod constants;
+
 use std::marker::PhantomData;
 
 use halo2_proofs::{
     circuit::{Layouter, SimpleFloorPlanner, Value},
     dev::MockProver,
-    plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
-    poly::Rotation,
+    plonk::{Circuit, ConstraintSystem, Error},
 };
 
-use ff::Field;
-use halo2_proofs::circuit::AssignedCell;
-use halo2_proofs::plonk::Fixed;
+use ff::{Field, PrimeField};
+
+use arithmetic::{ArithmeticChip, ArithmeticConfig, ArithmeticInstructions};
+
+use allow_list::{encode, load_allow_list, AllowListCircuit};
+use constants::{ConstantInstructions, Constants, ConstantsConfig};
 
 struct TestCircuit<F: Field> {
     _ph: PhantomData<F>,
@@ -19,55 +24,11 @@ struct TestCircuit<F: Field> {
 #[derive(Clone, Debug)]
 struct TestConfig<F: Field + Clone> {
     _ph: PhantomData<F>,
-    fixed: Column<Fixed>, // Tipo de columna Fixed, es el caso general del selector q permite solo 0 ó 1
-    advice: Column<Advice>,
+    arithmetic: ArithmeticConfig,
+    constants: ConstantsConfig,
 }
 
-impl<F: Field> TestCircuit<F> {
-
-    fn constrain_cell_to_be_equal_to_fixed(
-        &self,
-        config: &<Self as Circuit<F>>::Config,
-        mut layouter: impl Layouter<F>,
-        value: F,
-        variable: AssignedCell<F,F>
-    ) -> Result<(), Error> {
-        layouter.assign_region(
-            || "fixed",
-            |mut region| {
-                let fixed_cell = region.assign_fixed(
-                    ||"assign fixed",
-                    config.fixed,
-                    0,
-                    || Value::known(value)
-                )?;
-                region.constrain_equal(variable.cell(), fixed_cell.cell())?;
-                Ok(())
-            }
-        )
-    }
-
-    fn unconstrained(
-        &self,
-        config: &<Self as Circuit<F>>::Config,
-        layouter: &mut impl Layouter<F>,
-        value: Value<F>,
-    ) -> Result<AssignedCell<F, F>, Error> {
-        layouter.assign_region(
-            || "free variable",
-            |mut region| {
-                region.assign_advice(
-                    || "assign w0",
-                    config.advice,
-                    0,
-                    || value,
-                )
-            },
-        )
-    }
-}
-
-impl<F: Field> Circuit<F> for TestCircuit<F> {
+impl<F: PrimeField> Circuit<F> for TestCircuit<F> {
     type Config = TestConfig<F>;
     type FloorPlanner = SimpleFloorPlanner;
 
@@ -79,23 +40,19 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        let q_fixed = meta.complex_selector();
+        // columna Fixed donde el layouter pone las constantes del circuito (`enable_constant`)
         let fixed = meta.fixed_column();
         let advice = meta.advice_column();
-        meta.enable_equality(advice);
-        meta.enable_equality(fixed);
 
-        meta.create_gate("equal-constant", |meta| {
-            let w = meta.query_advice(advice, Rotation::cur());
-            let c = meta.query_fixed(fixed, Rotation::cur());
-            let q_fixed = meta.query_selector(q_fixed);
-            vec![q_fixed * (w - c)]
-        });
+        // nothing is public
+        let arithmetic = ArithmeticChip::configure(meta, advice, None);
+        // no gate needed, see `ConstantsConfig::configure`
+        let constants = ConstantsConfig::configure(meta, advice, fixed);
 
         TestConfig {
             _ph: PhantomData,
-            fixed,
-            advice,
+            arithmetic,
+            constants,
         }
     }
 
@@ -104,9 +61,15 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
         config: Self::Config,
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
-        let cell = self.unconstrained(&config, &mut layouter, self.secret.clone())?;
-        let _constant = self.constrain_cell_to_be_equal_to_fixed(&config, layouter, F::ONE, cell);
-        Ok(())
+        let chip = ArithmeticChip::construct(config.arithmetic.clone());
+        let mut constants = Constants::new(config.constants.clone());
+
+        // secret == 1 and secret * secret == 1, the constant is assigned only once
+        let cell = chip.load_private(&mut layouter, self.secret)?;
+        chip.constrain_constant(&mut layouter, &mut constants, &cell, F::ONE)?;
+
+        let square = chip.mul(&mut layouter, &cell, &cell)?;
+        chip.constrain_constant(&mut layouter, &mut constants, &square, F::ONE)
     }
 }
 
@@ -119,4 +82,42 @@ fn main() {
     };
     let prover = MockProver::run(8, &circuit, vec![]).unwrap();
     prover.verify().unwrap();
+
+    // the secret is one of the allow-listed codes
+    let allow_list = load_allow_list(concat!(env!("CARGO_MANIFEST_DIR"), "/allow_list.txt"))
+        .unwrap_or_else(|e| panic!("{}", e));
+    let circuit = AllowListCircuit::<Fr> {
+        allow_list,
+        secret: Value::known(Fr::from(encode("UY").unwrap())),
+    };
+    let prover = MockProver::run(8, &circuit, vec![]).unwrap();
+    prover.verify().unwrap();
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use halo2_proofs::dev::VerifyFailure;
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+
+    /// Runs `circuit`, which has no public inputs, for the tests of every module.
+    pub(crate) fn verify(circuit: &impl Circuit<Fr>) -> Result<(), Vec<VerifyFailure>> {
+        MockProver::run(8, circuit, vec![]).unwrap().verify()
+    }
+
+    #[test]
+    fn test_should_accept_one(){
+        let circuit = TestCircuit::<Fr> { _ph: PhantomData, secret: Value::known(Fr::ONE) };
+        verify(&circuit).unwrap();
+    }
+
+    #[test]
+    fn test_should_not_accept_other_values(){
+        // -1 passes the square check, but not the first one
+        for secret in [Fr::ZERO, -Fr::ONE, Fr::from(2)] {
+            let circuit = TestCircuit::<Fr> { _ph: PhantomData, secret: Value::known(secret) };
+            assert!(verify(&circuit).is_err());
+        }
+    }
 }
@@ -65,23 +65,18 @@ impl<F: Field> PlonkChip<F> {
         layouter: &mut impl Layouter<F>,
         lhs: AssignedCell<F, F>,
         rhs: AssignedCell<F, F>,
-    ) -> Option<AssignedCell<F, F>> {
-        let mut result_cell = None;
-        let _ = layouter.assign_region(
+    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+        layouter.assign_region(
             || "multiplication",
             |mut region| {
-                Self::_assign_plonk_regions(&mut region, config, F::ZERO, F::ZERO, F::ONE, -F::ONE, F::ZERO);
+                Self::_assign_plonk_regions(&mut region, config, F::ZERO, F::ZERO, F::ONE, -F::ONE, F::ZERO)?;
 
                 let a = lhs.copy_advice(|| "Copy a", &mut region, config.a, 0)?;
                 let b = rhs.copy_advice(|| "Copy b", &mut region, config.b, 0)?;
                 let c_value = a.value().cloned() * b.value().cloned();
-                let c = region.assign_advice(|| "Result", config.c, 0, || c_value)?;
-
-                result_cell = Some(c);
-                Ok(())
+                region.assign_advice(|| "Result", config.c, 0, || c_value)
             },
-        );
-        result_cell
+        )
     }
 
     fn add_cells(
@@ -90,23 +85,18 @@ impl<F: Field> PlonkChip<F> {
         layouter: &mut impl Layouter<F>,
         lhs: AssignedCell<F, F>,
         rhs: AssignedCell<F, F>,
-    ) -> Option<AssignedCell<F, F>> {
-        let mut result_cell = None;
-        let _ = layouter.assign_region(
+    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+        layouter.assign_region(
             || "addition",
             |mut region| {
-                Self::_assign_plonk_regions(&mut region, config, F::ONE, F::ONE, F::ZERO, -F::ONE, F::ZERO);
+                Self::_assign_plonk_regions(&mut region, config, F::ONE, F::ONE, F::ZERO, -F::ONE, F::ZERO)?;
 
                 let a = lhs.copy_advice(|| "Copy a", &mut region, config.a, 0)?;
                 let b = rhs.copy_advice(|| "Copy b", &mut region, config.b, 0)?;
                 let c_value = a.value().cloned() + b.value().cloned();
-                let c = region.assign_advice(|| "Result", config.c, 0, || c_value)?;
-
-                result_cell = Some(c);
-                Ok(())
+                region.assign_advice(|| "Result", config.c, 0, || c_value)
             },
-        );
-        result_cell
+        )
     }
 
     fn new_constant_cell(
@@ -114,20 +104,16 @@ impl<F: Field> PlonkChip<F> {
         config: &TestConfig<F>,
         layouter: &mut impl Layouter<F>,
         constant_value: F,
-    ) -> Option<AssignedCell<F, F>> {
-        let mut result_cell = None;
-        let _ = layouter.assign_region(
+    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+        layouter.assign_region(
             || "constant",
             |mut region| {
-                Self::_assign_plonk_regions(&mut region, config, F::ZERO, F::ZERO, F::ZERO, -F::ONE, constant_value);
+                Self::_assign_plonk_regions(&mut region, config, F::ZERO, F::ZERO, F::ZERO, -F::ONE, constant_value)?;
 
-                let c = region.assign_advice(|| "Result", config.c,
-                                             0, || Value::known(constant_value))?;
-                result_cell = Some(c);
-                Ok(())
+                region.assign_advice(|| "Result", config.c,
+                                     0, || Value::known(constant_value))
             },
-        );
-        result_cell
+        )
     }
 
     fn enforce_cells_to_be_equal(
@@ -136,27 +122,28 @@ impl<F: Field> PlonkChip<F> {
         layouter: &mut impl Layouter<F>,
         lhs: AssignedCell<F, F>,
         rhs: AssignedCell<F, F>,
-    ) {
-        let _ = layouter.assign_region(
+    ) -> Result<(), plonk::Error> {
+        layouter.assign_region(
             || "addition",
             |mut region| {
-                Self::_assign_plonk_regions(&mut region, config, F::ONE, -F::ONE, F::ZERO, F::ZERO, F::ZERO);
+                Self::_assign_plonk_regions(&mut region, config, F::ONE, -F::ONE, F::ZERO, F::ZERO, F::ZERO)?;
 
                 let _a = lhs.copy_advice(|| "Copy a", &mut region, config.a, 0)?;
                 let _b = rhs.copy_advice(|| "Copy b", &mut region, config.b, 0)?;
 
                 Ok(())
             },
-        );
+        )
     }
 
     fn _assign_plonk_regions(region: &mut Region<F>, config: &TestConfig<F>,
-        ql: F, qr: F, qm: F, qo: F, qc: F) {
-        let _ql = region.assign_fixed(|| "Ql", config.plonk_chip.ql, 0, || Value::known(ql));
-        let _qr = region.assign_fixed(|| "Qr", config.plonk_chip.qr, 0, || Value::known(qr));
-        let _qm = region.assign_fixed(|| "Qm", config.plonk_chip.qm, 0, || Value::known(qm));
-        let _qo = region.assign_fixed(|| "Qo", config.plonk_chip.qo, 0, || Value::known(qo));
-        let _qc = region.assign_fixed(|| "Qc", config.plonk_chip.qc, 0, || Value::known(qc));
+        ql: F, qr: F, qm: F, qo: F, qc: F) -> Result<(), plonk::Error> {
+        region.assign_fixed(|| "Ql", config.plonk_chip.ql, 0, || Value::known(ql))?;
+        region.assign_fixed(|| "Qr", config.plonk_chip.qr, 0, || Value::known(qr))?;
+        region.assign_fixed(|| "Qm", config.plonk_chip.qm, 0, || Value::known(qm))?;
+        region.assign_fixed(|| "Qo", config.plonk_chip.qo, 0, || Value::known(qo))?;
+        region.assign_fixed(|| "Qc", config.plonk_chip.qc, 0, || Value::known(qc))?;
+        Ok(())
     }
 }
 
@@ -229,16 +216,16 @@ impl<F: Field + PrimeField> Circuit<F> for TestCircuit<F> {
         let z = self.unconstrained(&config, &mut layouter, self.z)?;
 
         // aux1 == x*y
-        let aux1 = config.plonk_chip.multiply_cells(&config, &mut layouter, x, y.clone()).unwrap();
+        let aux1 = config.plonk_chip.multiply_cells(&config, &mut layouter, x, y.clone())?;
         // aux2 == aux1 + z
-        let aux2 = config.plonk_chip.add_cells(&config, &mut layouter, aux1.clone(), z.clone()).unwrap();
+        let aux2 = config.plonk_chip.add_cells(&config, &mut layouter, aux1.clone(), z.clone())?;
         // aux3 == aux1 * aux2
-        let aux3 = config.plonk_chip.multiply_cells(&config, &mut layouter, aux1, aux2).unwrap();
+        let aux3 = config.plonk_chip.multiply_cells(&config, &mut layouter, aux1, aux2)?;
         // y == z
-        config.plonk_chip.enforce_cells_to_be_equal(&config, &mut layouter, y, z);
+        config.plonk_chip.enforce_cells_to_be_equal(&config, &mut layouter, y, z)?;
         // aux3 == 8
-        let constant_8 = config.plonk_chip.new_constant_cell(&config, &mut layouter, F::from_u128(8)).unwrap();
-        config.plonk_chip.enforce_cells_to_be_equal(&config, &mut layouter, aux3, constant_8);
+        let constant_8 = config.plonk_chip.new_constant_cell(&config, &mut layouter, F::from_u128(8))?;
+        config.plonk_chip.enforce_cells_to_be_equal(&config, &mut layouter, aux3, constant_8)?;
 
         Ok(())
     }
@@ -0,0 +1,223 @@
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Region, SimpleFloorPlanner, Value},
+    plonk::{self, Circuit, ConstraintSystem},
+};
+
+use ff::{Field, PrimeField};
+
+use crate::public_inputs::{PublicInputSchema, PublicInputs};
+use crate::trace::Operation;
+use crate::{PlonkChip, PlonkInstructions, TestCircuit, TestConfig};
+
+/// Lays out PlonkChip rows one after the other in a single region, instead of a region per
+/// operation. The operations are the ones of `PlonkInstructions`; operands can be cells of this
+/// or any other region.
+pub(crate) struct PlonkRegion<'a, 'r, F: Field> {
+    config: &'a TestConfig<F>,
+    region: &'a mut Region<'r, F>,
+    offset: usize,
+    /// for a recording chip, which can only record them once the region is laid out
+    steps: Vec<BatchStep<F>>,
+}
+
+/// (operation, inputs, output)
+type BatchStep<F> = (Operation, Vec<AssignedCell<F, F>>, Option<AssignedCell<F, F>>);
+
+impl<F: Field> PlonkRegion<'_, '_, F> {
+    /// Assigns the next row: `lhs` to `a`, `rhs` to `b` and `out` to `c`.
+    fn row(
+        &mut self,
+        op: Operation,
+        q: [F; 5],
+        lhs: Option<&AssignedCell<F, F>>,
+        rhs: Option<&AssignedCell<F, F>>,
+        out: Option<Value<F>>,
+    ) -> Result<Option<AssignedCell<F, F>>, plonk::Error> {
+        let (config, row) = (self.config, self.offset);
+        PlonkChip::_assign_plonk_row(self.region, config, row, q)?;
+        if let Some(lhs) = lhs {
+            lhs.copy_advice(|| "Copy a", self.region, config.a, row)?;
+        }
+        if let Some(rhs) = rhs {
+            rhs.copy_advice(|| "Copy b", self.region, config.b, row)?;
+        }
+        let out = out.map(|value| self.region.assign_advice(|| "Result", config.c, row, || value)).transpose()?;
+        self.offset += 1;
+        if config.plonk_chip.trace.is_some() {
+            let inputs = lhs.into_iter().chain(rhs).cloned().collect();
+            self.steps.push((op, inputs, out.clone()));
+        }
+        Ok(out)
+    }
+
+    fn binary(
+        &mut self,
+        op: Operation,
+        q: [F; 5],
+        lhs: &AssignedCell<F, F>,
+        rhs: &AssignedCell<F, F>,
+        value: impl Fn(F, F) -> F,
+    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+        let value = lhs.value().zip(rhs.value()).map(|(lhs, rhs)| value(*lhs, *rhs));
+        // a row with an output always returns it
+        Ok(self.row(op, q, Some(lhs), Some(rhs), Some(value))?.unwrap())
+    }
+
+    pub fn multiply(&mut self, lhs: &AssignedCell<F, F>, rhs: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, plonk::Error> {
+        self.binary(Operation::Multiply, [F::ZERO, F::ZERO, F::ONE, -F::ONE, F::ZERO], lhs, rhs, |a, b| a * b)
+    }
+
+    pub fn add(&mut self, lhs: &AssignedCell<F, F>, rhs: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, plonk::Error> {
+        self.binary(Operation::Add, [F::ONE, F::ONE, F::ZERO, -F::ONE, F::ZERO], lhs, rhs, |a, b| a + b)
+    }
+
+    pub fn subtract(&mut self, lhs: &AssignedCell<F, F>, rhs: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, plonk::Error> {
+        self.binary(Operation::Subtract, [F::ONE, -F::ONE, F::ZERO, -F::ONE, F::ZERO], lhs, rhs, |a, b| a - b)
+    }
+
+    pub fn constant(&mut self, value: F) -> Result<AssignedCell<F, F>, plonk::Error> {
+        let q = [F::ZERO, F::ZERO, F::ZERO, -F::ONE, value];
+        Ok(self.row(Operation::Constant, q, None, None, Some(Value::known(value)))?.unwrap())
+    }
+
+    pub fn assert_equal(&mut self, lhs: &AssignedCell<F, F>, rhs: &AssignedCell<F, F>) -> Result<(), plonk::Error> {
+        self.row(Operation::Equal, [F::ONE, -F::ONE, F::ZERO, F::ZERO, F::ZERO], Some(lhs), Some(rhs), None)?;
+        Ok(())
+    }
+}
+
+impl<F: Field> PlonkChip<F> {
+    /// Runs `ops` on a single region. `ops` runs inside the region closure, see `PlonkChip::record`.
+    pub(crate) fn batch<T>(
+        &self,
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        name: &'static str,
+        mut ops: impl FnMut(&mut PlonkRegion<F>) -> Result<T, plonk::Error>,
+    ) -> Result<T, plonk::Error> {
+        let mut steps = vec![];
+        let result = layouter.assign_region(
+            || name,
+            |mut region| {
+                let mut rows = PlonkRegion { config, region: &mut region, offset: 0, steps: vec![] };
+                let result = ops(&mut rows)?;
+                // only the steps of the last run
+                steps = rows.steps;
+                Ok(result)
+            },
+        )?;
+        for (op, inputs, output) in &steps {
+            self.record(*op, &inputs.iter().collect::<Vec<_>>(), output.as_ref());
+        }
+        Ok(result)
+    }
+}
+
+/// `x^exponent` as `exponent - 1` multiplications, in one region if `batched` or in a region
+/// each otherwise. The result is the only public input.
+pub(crate) struct PowerCircuit<F: Field> {
+    pub x: Value<F>,
+    pub exponent: usize,
+    pub batched: bool,
+}
+
+impl<F: Field> PublicInputs for PowerCircuit<F> {
+    fn public_input_schema(&self) -> PublicInputSchema {
+        PublicInputSchema::new(["x^exponent"]).unwrap()
+    }
+}
+
+impl<F: Field + PrimeField> Circuit<F> for PowerCircuit<F> {
+    type Config = TestConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        PowerCircuit { x: Value::unknown(), exponent: self.exponent, batched: self.batched }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        TestCircuit::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), plonk::Error> {
+        let chip = &config.plonk_chip;
+        let expected = chip.bind_named_public_inputs(&config, &mut layouter, &self.public_input_schema())?;
+        let expected = expected.get("x^exponent")?;
+        let x = layouter.assign_region(
+            || "Private input",
+            |mut region| region.assign_advice(|| "Private input", config.a, 0, || self.x),
+        )?;
+
+        if self.batched {
+            chip.batch(&config, &mut layouter, "power", |rows| {
+                let mut power = x.clone();
+                for _ in 1..self.exponent {
+                    power = rows.multiply(&power, &x)?;
+                }
+                rows.assert_equal(&power, &expected)
+            })
+        } else {
+            let mut power = x.clone();
+            for _ in 1..self.exponent {
+                power = chip.multiply_cells(&config, &mut layouter, power, x.clone())?;
+            }
+            chip.enforce_cells_to_be_equal(&config, &mut layouter, power, expected)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+    use crate::tests::verify;
+
+    #[test]
+    fn test_should_give_same_results_batched_or_not(){
+        for batched in [false, true] {
+            assert!(verify(8, &PowerCircuit { x: Value::known(Fr::from(3)), exponent: 5, batched }, &[Fr::from(243)]).is_ok());
+            assert!(verify(8, &PowerCircuit { x: Value::known(Fr::from(3)), exponent: 5, batched }, &[Fr::from(244)]).is_err());
+        }
+        assert!(verify(8, &PowerCircuit { x: Value::known(Fr::from(2)), exponent: 60, batched: true }, &[Fr::from(1 << 60)]).is_ok());
+    }
+
+    #[test]
+    fn test_should_copy_cells_between_rows_of_a_batch(){
+        struct ConstantsCircuit;
+
+        impl Circuit<Fr> for ConstantsCircuit {
+            type Config = TestConfig<Fr>;
+            type FloorPlanner = SimpleFloorPlanner;
+            type Params = ();
+
+            fn without_witnesses(&self) -> Self {
+                ConstantsCircuit
+            }
+
+            fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+                TestCircuit::configure(meta)
+            }
+
+            fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fr>) -> Result<(), plonk::Error> {
+                config.plonk_chip.batch(&config, &mut layouter, "constants", |rows| {
+                    let two = rows.constant(Fr::from(2))?;
+                    let three = rows.constant(Fr::from(3))?;
+                    let five = rows.add(&two, &three)?;
+                    let six = rows.multiply(&two, &three)?;
+                    let one = rows.subtract(&six, &five)?;
+                    let expected = rows.constant(Fr::from(1))?;
+                    rows.assert_equal(&one, &expected)
+                })
+            }
+        }
+
+        MockProver::run(8, &ConstantsCircuit, vec![vec![]]).unwrap().verify().unwrap();
+    }
+}
@@ -0,0 +1,230 @@
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    plonk::{self, Circuit, ConstraintSystem},
+};
+
+use ff::{Field, PrimeField};
+
+use crate::public_inputs::{PublicInputSchema, PublicInputs};
+use crate::{PlonkChip, PlonkInstructions, TestCircuit, TestConfig};
+
+/// A cell known to hold 0 or 1. It is only built by gadgets whose constraints prove it, such as
+/// `assert_bool` or the ones below, so they don't check their inputs again.
+#[derive(Clone, Debug)]
+pub(crate) struct AssignedBool<F: Field>(pub(crate) AssignedCell<F, F>);
+
+impl<F: Field> AssignedBool<F> {
+    pub fn cell(&self) -> AssignedCell<F, F> {
+        self.0.clone()
+    }
+}
+
+impl<F: Field> PlonkChip<F> {
+    /// `x * x - x = 0`
+    pub(crate) fn assert_bool(
+        &self,
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        x: AssignedCell<F, F>,
+    ) -> Result<AssignedBool<F>, plonk::Error> {
+        layouter.assign_region(
+            || "booleanity",
+            |mut region| {
+                Self::_assign_plonk_regions(&mut region, config, -F::ONE, F::ZERO, F::ONE, F::ZERO, F::ZERO)?;
+
+                x.copy_advice(|| "Copy a", &mut region, config.a, 0)?;
+                x.copy_advice(|| "Copy b", &mut region, config.b, 0)?;
+                Ok(())
+            },
+        )?;
+        Ok(AssignedBool(x))
+    }
+
+    /// `ql * a + qr * b + qm * a * b + qc`, in `c`. Boolean for the coefficients used below.
+    #[allow(clippy::too_many_arguments)]
+    fn boolean_row(
+        &self,
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        name: &'static str,
+        lhs: &AssignedBool<F>,
+        rhs: Option<&AssignedBool<F>>,
+        ql: F,
+        qr: F,
+        qm: F,
+        qc: F,
+    ) -> Result<AssignedBool<F>, plonk::Error> {
+        let cell = layouter.assign_region(
+            || name,
+            |mut region| {
+                Self::_assign_plonk_regions(&mut region, config, ql, qr, qm, -F::ONE, qc)?;
+
+                let a = lhs.0.copy_advice(|| "Copy a", &mut region, config.a, 0)?;
+                let a = a.value().cloned();
+                let c_value = match rhs {
+                    Some(rhs) => {
+                        let b = rhs.0.copy_advice(|| "Copy b", &mut region, config.b, 0)?;
+                        a.zip(b.value().cloned()).map(|(a, b)| a * ql + b * qr + a * b * qm + qc)
+                    }
+                    None => a.map(|a| a * ql + qc),
+                };
+                region.assign_advice(|| "Result", config.c, 0, || c_value)
+            },
+        )?;
+        Ok(AssignedBool(cell))
+    }
+
+    /// `a * b`
+    pub(crate) fn and(
+        &self,
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        a: &AssignedBool<F>,
+        b: &AssignedBool<F>,
+    ) -> Result<AssignedBool<F>, plonk::Error> {
+        self.boolean_row(config, layouter, "and", a, Some(b), F::ZERO, F::ZERO, F::ONE, F::ZERO)
+    }
+
+    /// `a + b - a * b`
+    pub(crate) fn or(
+        &self,
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        a: &AssignedBool<F>,
+        b: &AssignedBool<F>,
+    ) -> Result<AssignedBool<F>, plonk::Error> {
+        self.boolean_row(config, layouter, "or", a, Some(b), F::ONE, F::ONE, -F::ONE, F::ZERO)
+    }
+
+    /// `a + b - 2 * a * b`
+    pub(crate) fn xor(
+        &self,
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        a: &AssignedBool<F>,
+        b: &AssignedBool<F>,
+    ) -> Result<AssignedBool<F>, plonk::Error> {
+        self.boolean_row(config, layouter, "xor", a, Some(b), F::ONE, F::ONE, -F::ONE.double(), F::ZERO)
+    }
+
+    /// `1 - a`
+    pub(crate) fn not(
+        &self,
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        a: &AssignedBool<F>,
+    ) -> Result<AssignedBool<F>, plonk::Error> {
+        self.boolean_row(config, layouter, "not", a, None, -F::ONE, F::ZERO, F::ZERO, F::ONE)
+    }
+
+    /// `cond ? when_true : when_false`, as `when_false + cond * (when_true - when_false)`.
+    pub(crate) fn select(
+        &self,
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        cond: &AssignedBool<F>,
+        when_true: AssignedCell<F, F>,
+        when_false: AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+        let difference = self.subtract_cells(config, layouter, when_true, when_false.clone())?;
+        let offset = self.multiply_cells(config, layouter, cond.cell(), difference)?;
+        self.add_cells(config, layouter, when_false, offset)
+    }
+}
+
+/// Public inputs: `[a and b, a or b, a xor b, not a, a ? 10 : 20]`, for private bits `a` and `b`.
+pub(crate) struct BooleanCircuit<F: Field> {
+    pub a: Value<F>,
+    pub b: Value<F>,
+}
+
+impl<F: Field> PublicInputs for BooleanCircuit<F> {
+    fn public_input_schema(&self) -> PublicInputSchema {
+        PublicInputSchema::new(["and", "or", "xor", "not", "select"]).unwrap()
+    }
+}
+
+impl<F: Field + PrimeField> Circuit<F> for BooleanCircuit<F> {
+    type Config = TestConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        BooleanCircuit { a: Value::unknown(), b: Value::unknown() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        TestCircuit::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), plonk::Error> {
+        let chip = &config.plonk_chip;
+        let expected = chip.bind_named_public_inputs(&config, &mut layouter, &self.public_input_schema())?;
+
+        let mut bits = vec![];
+        for value in [self.a, self.b] {
+            let cell = layouter.assign_region(
+                || "Private input",
+                |mut region| region.assign_advice(|| "Private input", config.a, 0, || value),
+            )?;
+            bits.push(chip.assert_bool(&config, &mut layouter, cell)?);
+        }
+        let (a, b) = (&bits[0], &bits[1]);
+
+        let ten = chip.new_constant_cell(&config, &mut layouter, F::from(10))?;
+        let twenty = chip.new_constant_cell(&config, &mut layouter, F::from(20))?;
+        let results = [
+            ("and", chip.and(&config, &mut layouter, a, b)?.cell()),
+            ("or", chip.or(&config, &mut layouter, a, b)?.cell()),
+            ("xor", chip.xor(&config, &mut layouter, a, b)?.cell()),
+            ("not", chip.not(&config, &mut layouter, a)?.cell()),
+            ("select", chip.select(&config, &mut layouter, a, ten, twenty)?),
+        ];
+        for (name, result) in results {
+            chip.enforce_cells_to_be_equal(&config, &mut layouter, result, expected.get(name)?)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+    use crate::tests::verify;
+
+    fn circuit(a: u64, b: u64) -> BooleanCircuit<Fr> {
+        BooleanCircuit { a: Value::known(Fr::from(a)), b: Value::known(Fr::from(b)) }
+    }
+
+    #[test]
+    fn test_should_follow_truth_tables(){
+        for a in 0..2 {
+            for b in 0..2 {
+                let select = if a == 1 { 10 } else { 20 };
+                assert!(verify(8, &circuit(a, b), &[a & b, a | b, a ^ b, 1 - a, select].map(Fr::from)).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn test_should_not_accept_wrong_results(){
+        assert!(verify(8, &circuit(1, 0), &[1, 1, 1, 0, 10].map(Fr::from)).is_err());
+        assert!(verify(8, &circuit(1, 0), &[0, 1, 1, 0, 20].map(Fr::from)).is_err());
+    }
+
+    #[test]
+    fn test_should_not_accept_non_boolean_inputs(){
+        // every result is consistent with a = 2, only booleanity fails
+        let circuit = BooleanCircuit::<Fr> { a: Value::known(Fr::from(2)), b: Value::known(Fr::from(0)) };
+        let public_inputs = vec![Fr::from(0), Fr::from(2), Fr::from(2), -Fr::ONE, Fr::from(0)];
+        let failures = MockProver::run(8, &circuit, vec![public_inputs]).unwrap().verify().unwrap_err();
+        assert!(failures.iter().any(|failure| failure.to_string().contains("('booleanity')")), "{:?}", failures);
+    }
+}
@@ -0,0 +1,502 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    plonk::{self, Circuit, ConstraintSystem},
+};
+
+use ff::{Field, PrimeField};
+
+use crate::public_inputs::{PublicInputSchema, PublicInputs};
+use crate::{PlonkChip, PlonkInstructions, TestCircuit, TestConfig};
+
+#[derive(Debug)]
+pub enum CircomError {
+    Io(io::Error),
+    Format(String),
+    /// The file was generated for a prime other than the modulus of `F`.
+    FieldMismatch,
+    WitnessLength { expected: usize, found: usize },
+}
+
+impl fmt::Display for CircomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CircomError::Io(e) => write!(f, "could not read the file: {}", e),
+            CircomError::Format(message) => write!(f, "invalid file: {}", message),
+            CircomError::FieldMismatch => write!(f, "the file uses a different prime field"),
+            CircomError::WitnessLength { expected, found } => {
+                write!(f, "expected {} witness values, found {}", expected, found)
+            }
+        }
+    }
+}
+
+/// `(wire, coefficient)` pairs. Wire 0 is always the constant 1.
+pub type LinearCombination<F> = Vec<(usize, F)>;
+
+/// `a * b = c`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Constraint<F> {
+    pub a: LinearCombination<F>,
+    pub b: LinearCombination<F>,
+    pub c: LinearCombination<F>,
+}
+
+/// The constraints of a circom circuit. Wires are numbered as in the witness:
+///
+/// | 0 | 1 ..= n_public                | n_public + 1 ..          |
+/// | 1 | public outputs, public inputs | private inputs, internal |
+#[derive(Clone, Debug, PartialEq)]
+pub struct R1cs<F> {
+    pub n_wires: usize,
+    pub n_public: usize,
+    pub constraints: Vec<Constraint<F>>,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], CircomError> {
+        let end = self.position.checked_add(n).filter(|end| *end <= self.bytes.len());
+        let end = end.ok_or_else(|| CircomError::Format("unexpected end of file".to_string()))?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, CircomError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, CircomError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn usize(&mut self) -> Result<usize, CircomError> {
+        Ok(self.u32()? as usize)
+    }
+
+    /// Reads a little-endian `n8` byte integer.
+    fn field_element<F: PrimeField>(&mut self, n8: usize) -> Result<F, CircomError> {
+        let byte = F::from(256);
+        Ok(self.take(n8)?.iter().rev().fold(F::ZERO, |acc, b| acc * byte + F::from(*b as u64)))
+    }
+
+    /// Reads the `n8` field size and the prime that follows it, and checks it's the modulus of `F`.
+    fn field<F: PrimeField>(&mut self) -> Result<usize, CircomError> {
+        let n8 = self.usize()?;
+        let prime = self.take(n8)?;
+        let prime: String = prime.iter().rev().map(|b| format!("{:02x}", b)).collect();
+        let modulus = F::MODULUS.trim_start_matches("0x").to_lowercase();
+        if prime.trim_start_matches('0') != modulus.trim_start_matches('0') {
+            return Err(CircomError::FieldMismatch);
+        }
+        Ok(n8)
+    }
+}
+
+/// Both formats are `magic, version, n_sections` followed by `(type, size, content)` sections.
+fn sections<'a>(bytes: &'a [u8], magic: &[u8]) -> Result<Vec<(u32, &'a [u8])>, CircomError> {
+    let mut reader = Reader { bytes, position: 0 };
+    if reader.take(4)? != magic {
+        return Err(CircomError::Format(format!("missing {:?} magic", String::from_utf8_lossy(magic))));
+    }
+    let _version = reader.u32()?;
+    let n_sections = reader.u32()?;
+
+    let mut sections = vec![];
+    for _ in 0..n_sections {
+        let section_type = reader.u32()?;
+        let size = reader.u64()?;
+        let size = usize::try_from(size).map_err(|_| CircomError::Format("section too large".to_string()))?;
+        sections.push((section_type, reader.take(size)?));
+    }
+    Ok(sections)
+}
+
+fn section<'a>(sections: &[(u32, &'a [u8])], section_type: u32) -> Result<Reader<'a>, CircomError> {
+    sections
+        .iter()
+        .find(|(t, _)| *t == section_type)
+        .map(|(_, bytes)| Reader { bytes, position: 0 })
+        .ok_or_else(|| CircomError::Format(format!("missing section {}", section_type)))
+}
+
+pub fn parse_r1cs<F: PrimeField>(bytes: &[u8]) -> Result<R1cs<F>, CircomError> {
+    let sections = sections(bytes, b"r1cs")?;
+
+    let mut header = section(&sections, 1)?;
+    let n8 = header.field::<F>()?;
+    let n_wires = header.usize()?;
+    let n_public_outputs = header.usize()?;
+    let n_public_inputs = header.usize()?;
+    let _n_private_inputs = header.usize()?;
+    let _n_labels = header.u64()?;
+    let n_constraints = header.usize()?;
+
+    let mut body = section(&sections, 2)?;
+    let linear_combination = |body: &mut Reader| -> Result<LinearCombination<F>, CircomError> {
+        let n_terms = body.usize()?;
+        let mut terms = Vec::with_capacity(n_terms.min(n_wires));
+        for _ in 0..n_terms {
+            let wire = body.usize()?;
+            if wire >= n_wires {
+                return Err(CircomError::Format(format!("wire {} out of range", wire)));
+            }
+            terms.push((wire, body.field_element(n8)?));
+        }
+        Ok(terms)
+    };
+    let mut constraints = vec![];
+    for _ in 0..n_constraints {
+        let a = linear_combination(&mut body)?;
+        let b = linear_combination(&mut body)?;
+        let c = linear_combination(&mut body)?;
+        constraints.push(Constraint { a, b, c });
+    }
+
+    let n_public = n_public_outputs + n_public_inputs;
+    // wire 0 is the constant 1
+    if n_public >= n_wires {
+        return Err(CircomError::Format(format!("{} public wires out of {}", n_public, n_wires)));
+    }
+    Ok(R1cs { n_wires, n_public, constraints })
+}
+
+/// A witness for `r1cs` has a value per wire, and the first one is 1.
+pub fn check_witness<F: PrimeField>(r1cs: &R1cs<F>, witness: &[F]) -> Result<(), CircomError> {
+    if witness.len() != r1cs.n_wires {
+        return Err(CircomError::WitnessLength { expected: r1cs.n_wires, found: witness.len() });
+    }
+    if witness.first() != Some(&F::ONE) {
+        return Err(CircomError::Format("the first witness value must be 1".to_string()));
+    }
+    Ok(())
+}
+
+pub fn parse_witness<F: PrimeField>(bytes: &[u8]) -> Result<Vec<F>, CircomError> {
+    let sections = sections(bytes, b"wtns")?;
+
+    let mut header = section(&sections, 1)?;
+    let n8 = header.field::<F>()?;
+    let n_values = header.usize()?;
+
+    let mut values = section(&sections, 2)?;
+    (0..n_values).map(|_| values.field_element(n8)).collect()
+}
+
+pub fn load_r1cs<F: PrimeField>(path: impl AsRef<Path>) -> Result<R1cs<F>, CircomError> {
+    parse_r1cs(&fs::read(path).map_err(CircomError::Io)?)
+}
+
+/// Also checks that the witness is one for `r1cs`.
+pub fn load_witness<F: PrimeField>(path: impl AsRef<Path>, r1cs: &R1cs<F>) -> Result<Vec<F>, CircomError> {
+    let witness = parse_witness(&fs::read(path).map_err(CircomError::Io)?)?;
+    check_witness(r1cs, &witness)?;
+    Ok(witness)
+}
+
+impl<F: Field> PlonkChip<F> {
+    /// `ql * lhs + qr * rhs + qc`. Without `rhs` the `b` cell is left empty, as `qr` is 0.
+    fn linear_cells(
+        &self,
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        lhs: &AssignedCell<F, F>,
+        rhs: Option<(&AssignedCell<F, F>, F)>,
+        ql: F,
+        qc: F,
+    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+        layouter.assign_region(
+            || "linear combination",
+            |mut region| {
+                let qr = rhs.map_or(F::ZERO, |(_, qr)| qr);
+                Self::_assign_plonk_regions(&mut region, config, ql, qr, F::ZERO, -F::ONE, qc)?;
+
+                let a = lhs.copy_advice(|| "Copy a", &mut region, config.a, 0)?;
+                let mut c_value = a.value().map(|a| *a * ql + qc);
+                if let Some((rhs, qr)) = rhs {
+                    let b = rhs.copy_advice(|| "Copy b", &mut region, config.b, 0)?;
+                    c_value = c_value + b.value().map(|b| *b * qr);
+                }
+                region.assign_advice(|| "Result", config.c, 0, || c_value)
+            },
+        )
+    }
+
+    /// `lhs * rhs == out`, in a single row.
+    fn enforce_product(
+        &self,
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        lhs: AssignedCell<F, F>,
+        rhs: AssignedCell<F, F>,
+        out: AssignedCell<F, F>,
+    ) -> Result<(), plonk::Error> {
+        layouter.assign_region(
+            || "r1cs constraint",
+            |mut region| {
+                Self::_assign_plonk_regions(&mut region, config, F::ZERO, F::ZERO, F::ONE, -F::ONE, F::ZERO)?;
+
+                lhs.copy_advice(|| "Copy a", &mut region, config.a, 0)?;
+                rhs.copy_advice(|| "Copy b", &mut region, config.b, 0)?;
+                out.copy_advice(|| "Copy c", &mut region, config.c, 0)?;
+                Ok(())
+            },
+        )
+    }
+
+    /// Folds a linear combination into a single cell, two terms on the first row and one on
+    /// each of the following ones. The terms on wire 0 add up to the `qc` of the first row.
+    fn linear_combination(
+        &self,
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        wires: &[AssignedCell<F, F>],
+        linear_combination: &[(usize, F)],
+    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+        let mut constant = F::ZERO;
+        let mut terms = vec![];
+        for (wire, coefficient) in linear_combination {
+            match wire {
+                0 => constant += coefficient,
+                // wire 1 is the first element of `wires`
+                _ => terms.push((&wires[wire - 1], *coefficient)),
+            }
+        }
+
+        let mut terms = terms.into_iter();
+        let Some((first, coefficient)) = terms.next() else {
+            return self.new_constant_cell(config, layouter, constant);
+        };
+        if terms.len() == 0 && coefficient == F::ONE && constant == F::ZERO {
+            // the wire itself, no row needed
+            return Ok(first.clone());
+        }
+        let mut acc = self.linear_cells(config, layouter, first, terms.next(), coefficient, constant)?;
+        for term in terms {
+            acc = self.linear_cells(config, layouter, &acc, Some(term), F::ONE, F::ZERO)?;
+        }
+        Ok(acc)
+    }
+}
+
+/// Proves that a witness satisfies the constraints of a circom circuit. The public wires go to
+/// the instance column as `pub_0` to `pub_{n_public - 1}`, see `CircomCircuit::public_inputs`.
+/// The fields are private so that the witness always goes through `check_witness` in `new`.
+pub(crate) struct CircomCircuit<F: PrimeField> {
+    r1cs: R1cs<F>,
+    /// one value per wire, wire 0 included
+    witness: Vec<Value<F>>,
+}
+
+impl<F: PrimeField> CircomCircuit<F> {
+    pub fn new(r1cs: R1cs<F>, witness: &[F]) -> Result<Self, CircomError> {
+        check_witness(&r1cs, witness)?;
+        let witness = witness.iter().map(|value| Value::known(*value)).collect();
+        Ok(Self { r1cs, witness })
+    }
+
+    pub fn public_inputs(witness: &[F], r1cs: &R1cs<F>) -> Result<Vec<F>, CircomError> {
+        check_witness(r1cs, witness)?;
+        Ok(witness[1..=r1cs.n_public].to_vec())
+    }
+
+    /// An upper bound of the rows used by `synthesize`, to pick `k`.
+    pub fn rows(&self) -> usize {
+        let terms: usize = self.r1cs.constraints.iter().map(|c| c.a.len() + c.b.len() + c.c.len()).sum();
+        self.r1cs.n_wires + terms + 4 * self.r1cs.constraints.len()
+    }
+}
+
+/// circom doesn't keep the names of the signals in the r1cs file.
+impl<F: PrimeField> PublicInputs for CircomCircuit<F> {
+    fn public_input_schema(&self) -> PublicInputSchema {
+        PublicInputSchema::new((0..self.r1cs.n_public).map(|i| format!("pub_{}", i))).unwrap()
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for CircomCircuit<F> {
+    type Config = TestConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        CircomCircuit {
+            r1cs: self.r1cs.clone(),
+            witness: vec![Value::unknown(); self.witness.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        TestCircuit::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), plonk::Error> {
+        let chip = &config.plonk_chip;
+
+        // the public wires go first, see `bind_public_inputs`
+        let public = chip.bind_named_public_inputs(&config, &mut layouter, &self.public_input_schema())?;
+        let mut wires = public.cells().to_vec();
+        for value in &self.witness[self.r1cs.n_public + 1..] {
+            let cell = layouter.assign_region(
+                || "Private wire",
+                |mut region| region.assign_advice(|| "Private wire", config.a, 0, || *value),
+            )?;
+            wires.push(cell);
+        }
+
+        for constraint in &self.r1cs.constraints {
+            let a = chip.linear_combination(&config, &mut layouter, &wires, &constraint.a)?;
+            let b = chip.linear_combination(&config, &mut layouter, &wires, &constraint.b)?;
+            let c = chip.linear_combination(&config, &mut layouter, &wires, &constraint.c)?;
+            chip.enforce_product(&config, &mut layouter, a, b, c)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+    use crate::tests::verify;
+
+    fn element(value: Fr) -> Vec<u8> {
+        value.to_repr().as_ref().to_vec()
+    }
+
+    fn prime() -> Vec<u8> {
+        // p - 1 is even, so adding 1 doesn't carry
+        let mut prime = element(-Fr::ONE);
+        prime[0] += 1;
+        prime
+    }
+
+    fn file(magic: &[u8], sections: Vec<(u32, Vec<u8>)>) -> Vec<u8> {
+        let mut bytes = magic.to_vec();
+        bytes.extend(1u32.to_le_bytes());
+        bytes.extend((sections.len() as u32).to_le_bytes());
+        for (section_type, content) in sections {
+            bytes.extend(section_type.to_le_bytes());
+            bytes.extend((content.len() as u64).to_le_bytes());
+            bytes.extend(content);
+        }
+        bytes
+    }
+
+    fn field_header(prime: Vec<u8>) -> Vec<u8> {
+        let mut header = (prime.len() as u32).to_le_bytes().to_vec();
+        header.extend(prime);
+        header
+    }
+
+    /// `out <== a * b + 3 * c - 1`, with wires `[1, out, a, b, c, a * b]`:
+    ///
+    /// ```text
+    /// a * b = t
+    /// 1 * (t + 3c - 1) = out
+    /// ```
+    fn r1cs_file(prime: Vec<u8>) -> Vec<u8> {
+        let mut header = field_header(prime);
+        for n in [6u32, 1, 0, 3] {
+            header.extend(n.to_le_bytes());
+        }
+        header.extend(6u64.to_le_bytes());
+        header.extend(2u32.to_le_bytes());
+
+        let linear_combination = |terms: &[(u32, Fr)]| {
+            let mut bytes = (terms.len() as u32).to_le_bytes().to_vec();
+            for (wire, coefficient) in terms {
+                bytes.extend(wire.to_le_bytes());
+                bytes.extend(element(*coefficient));
+            }
+            bytes
+        };
+        let mut constraints = vec![];
+        constraints.extend(linear_combination(&[(2, Fr::ONE)]));
+        constraints.extend(linear_combination(&[(3, Fr::ONE)]));
+        constraints.extend(linear_combination(&[(5, Fr::ONE)]));
+        constraints.extend(linear_combination(&[(0, Fr::ONE)]));
+        constraints.extend(linear_combination(&[(5, Fr::ONE), (4, Fr::from(3)), (0, -Fr::ONE)]));
+        constraints.extend(linear_combination(&[(1, Fr::ONE)]));
+
+        file(b"r1cs", vec![(1, header), (2, constraints)])
+    }
+
+    fn witness_file(values: &[u64]) -> Vec<u8> {
+        let mut header = field_header(prime());
+        header.extend((values.len() as u32).to_le_bytes());
+        let values = values.iter().flat_map(|v| element(Fr::from(*v))).collect();
+        file(b"wtns", vec![(1, header), (2, values)])
+    }
+
+    /// The circuit of `r1cs_file` and its instance, read from `witness` as circom would.
+    fn circuit_with_instance(witness: &[u64]) -> (CircomCircuit<Fr>, Vec<Fr>) {
+        let r1cs = parse_r1cs::<Fr>(&r1cs_file(prime())).unwrap();
+        let witness = parse_witness::<Fr>(&witness_file(witness)).unwrap();
+        let public_inputs = CircomCircuit::public_inputs(&witness, &r1cs).unwrap();
+        (CircomCircuit::new(r1cs, &witness).unwrap(), public_inputs)
+    }
+
+    #[test]
+    fn test_should_parse_r1cs(){
+        let r1cs = parse_r1cs::<Fr>(&r1cs_file(prime())).unwrap();
+        assert_eq!(r1cs.n_wires, 6);
+        assert_eq!(r1cs.n_public, 1);
+        assert_eq!(r1cs.constraints.len(), 2);
+        assert_eq!(r1cs.constraints[1].b, vec![(5, Fr::ONE), (4, Fr::from(3)), (0, -Fr::ONE)]);
+
+        let circuit = CircomCircuit::new(r1cs, &[1, 17, 2, 3, 4, 6].map(Fr::from)).unwrap();
+        assert_eq!(circuit.public_input_schema(), PublicInputSchema::new(["pub_0"]).unwrap());
+    }
+
+    #[test]
+    fn test_should_reject_invalid_files(){
+        let mut other_prime = prime();
+        other_prime[1] ^= 1;
+        assert!(matches!(parse_r1cs::<Fr>(&r1cs_file(other_prime)), Err(CircomError::FieldMismatch)));
+        assert!(matches!(parse_witness::<Fr>(&r1cs_file(prime())), Err(CircomError::Format(_))));
+
+        let truncated = r1cs_file(prime());
+        let truncated = &truncated[..truncated.len() - 1];
+        assert!(matches!(parse_r1cs::<Fr>(truncated), Err(CircomError::Format(_))));
+
+        let r1cs = parse_r1cs::<Fr>(&r1cs_file(prime())).unwrap();
+        assert!(matches!(
+            CircomCircuit::public_inputs(&[Fr::ONE], &r1cs),
+            Err(CircomError::WitnessLength { expected: 6, found: 1 })
+        ));
+        assert!(matches!(
+            CircomCircuit::new(r1cs, &[Fr::ONE]),
+            Err(CircomError::WitnessLength { expected: 6, found: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_should_prove_valid_witness(){
+        // a = 2, b = 3, c = 4: out = 6 + 12 - 1
+        let (circuit, instance) = circuit_with_instance(&[1, 17, 2, 3, 4, 6]);
+        assert!(verify(6, &circuit, &instance).is_ok());
+    }
+
+    #[test]
+    fn test_should_not_prove_invalid_witness(){
+        let (circuit, instance) = circuit_with_instance(&[1, 18, 2, 3, 4, 6]);
+        assert!(verify(6, &circuit, &instance).is_err());
+        let (circuit, instance) = circuit_with_instance(&[1, 17, 2, 3, 4, 7]);
+        assert!(verify(6, &circuit, &instance).is_err());
+    }
+}
@@ -0,0 +1,399 @@
+use std::collections::HashMap;
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    plonk::{self, Circuit, ConstraintSystem},
+};
+
+use ff::{Field, PrimeField};
+
+use crate::batch::PlonkRegion;
+use crate::public_inputs::{PublicInputSchema, PublicInputs};
+use crate::{TestCircuit, TestConfig};
+
+/// A list of declarations and assertions, e.g.
+///
+/// ```text
+/// pub x; pub y; pub out; priv z;
+/// (x*y + z) * (x*y) == out;
+/// y == z
+/// ```
+///
+/// Public variables are read from the instance column, in the order they are declared.
+/// Private variables are witnesses, also given in the order they are declared.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Program {
+    public: Vec<String>,
+    private: Vec<String>,
+    assertions: Vec<(Expr, Expr)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Var(String),
+    Const(u64),
+    Neg(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CompileError {
+    /// The text could not be parsed; `position` is a byte offset into the source.
+    Parse { position: usize, message: String },
+    UndeclaredVariable(String),
+    DuplicateVariable(String),
+}
+
+impl Program {
+    pub fn parse(source: &str) -> Result<Self, CompileError> {
+        let mut parser = Parser { source, position: 0 };
+        let mut program = Program { public: vec![], private: vec![], assertions: vec![] };
+
+        while parser.peek().is_some() {
+            if parser.keyword("pub") {
+                let name = parser.identifier()?;
+                program.declare(name, true)?;
+            } else if parser.keyword("priv") {
+                let name = parser.identifier()?;
+                program.declare(name, false)?;
+            } else {
+                let lhs = parser.expr()?;
+                parser.expect("==")?;
+                let rhs = parser.expr()?;
+                program.assertions.push((lhs, rhs));
+            }
+            // the last statement doesn't need a ';'
+            if !parser.eat(";") && parser.peek().is_some() {
+                return Err(parser.error("expected ';'"));
+            }
+        }
+
+        for (lhs, rhs) in &program.assertions {
+            for name in lhs.vars().into_iter().chain(rhs.vars()) {
+                if !program.public.contains(name) && !program.private.contains(name) {
+                    return Err(CompileError::UndeclaredVariable(name.clone()));
+                }
+            }
+        }
+        Ok(program)
+    }
+
+    fn declare(&mut self, name: String, public: bool) -> Result<(), CompileError> {
+        if self.public.contains(&name) || self.private.contains(&name) {
+            return Err(CompileError::DuplicateVariable(name));
+        }
+        if public {
+            self.public.push(name);
+        } else {
+            self.private.push(name);
+        }
+        Ok(())
+    }
+}
+
+impl Expr {
+    fn vars(&self) -> Vec<&String> {
+        match self {
+            Expr::Var(name) => vec![name],
+            Expr::Const(_) => vec![],
+            Expr::Neg(expr) => expr.vars(),
+            Expr::Add(lhs, rhs) | Expr::Sub(lhs, rhs) | Expr::Mul(lhs, rhs) => {
+                let mut vars = lhs.vars();
+                vars.extend(rhs.vars());
+                vars
+            }
+        }
+    }
+
+    /// Writes one row of `rows` per operation and returns the cell holding the result.
+    fn compile<F: PrimeField>(
+        &self,
+        rows: &mut PlonkRegion<F>,
+        vars: &HashMap<String, AssignedCell<F, F>>,
+    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+        match self {
+            Expr::Var(name) => vars.get(name).cloned().ok_or(plonk::Error::Synthesis),
+            Expr::Const(c) => rows.constant(F::from(*c)),
+            Expr::Neg(expr) => {
+                let zero = rows.constant(F::ZERO)?;
+                let value = expr.compile(rows, vars)?;
+                rows.subtract(&zero, &value)
+            }
+            Expr::Add(lhs, rhs) => {
+                let lhs = lhs.compile(rows, vars)?;
+                let rhs = rhs.compile(rows, vars)?;
+                rows.add(&lhs, &rhs)
+            }
+            Expr::Sub(lhs, rhs) => {
+                let lhs = lhs.compile(rows, vars)?;
+                let rhs = rhs.compile(rows, vars)?;
+                rows.subtract(&lhs, &rhs)
+            }
+            Expr::Mul(lhs, rhs) => {
+                let lhs = lhs.compile(rows, vars)?;
+                let rhs = rhs.compile(rows, vars)?;
+                rows.multiply(&lhs, &rhs)
+            }
+        }
+    }
+}
+
+/// Recursive descent parser for
+///
+/// ```text
+/// program   := (statement (';' statement)* ';'?)?
+/// statement := 'pub' name | 'priv' name | expr '==' expr
+/// expr      := term (('+' | '-') term)*
+/// term      := factor ('*' factor)*
+/// factor    := '-' factor | number | name | '(' expr ')'
+/// ```
+struct Parser<'a> {
+    source: &'a str,
+    position: usize,
+}
+
+const KEYWORDS: [&str; 2] = ["pub", "priv"];
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &str) -> CompileError {
+        CompileError::Parse { position: self.position, message: message.to_string() }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.source[self.position..].chars().next() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.position += c.len_utf8();
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.source[self.position..].chars().next()
+    }
+
+    fn eat(&mut self, expected: &str) -> bool {
+        self.skip_whitespace();
+        if self.source[self.position..].starts_with(expected) {
+            self.position += expected.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), CompileError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", expected)))
+        }
+    }
+
+    /// Consumes `keyword` only if it is a whole word, so `private_key` is still a name.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let start = self.position;
+        if self.word() == Some(keyword) {
+            return true;
+        }
+        self.position = start;
+        false
+    }
+
+    fn word(&mut self) -> Option<&'a str> {
+        self.skip_whitespace();
+        let start = self.position;
+        for (i, c) in self.source[start..].char_indices() {
+            if !(c.is_ascii_alphanumeric() || c == '_') || (i == 0 && c.is_ascii_digit()) {
+                break;
+            }
+            self.position = start + i + c.len_utf8();
+        }
+        (self.position > start).then(|| &self.source[start..self.position])
+    }
+
+    fn identifier(&mut self) -> Result<String, CompileError> {
+        let name = self.word().ok_or_else(|| self.error("expected a name"))?;
+        let start = self.position - name.len();
+        if KEYWORDS.contains(&name) {
+            return Err(CompileError::Parse { position: start, message: format!("'{}' is a keyword", name) });
+        }
+        Ok(name.to_string())
+    }
+
+    fn number(&mut self) -> Result<u64, CompileError> {
+        self.skip_whitespace();
+        let start = self.position;
+        let digits = self.source[start..].chars().take_while(|c| c.is_ascii_digit()).count();
+        if digits == 0 {
+            return Err(self.error("expected a number"));
+        }
+        self.position += digits;
+        self.source[start..self.position]
+            .parse()
+            .map_err(|_| CompileError::Parse { position: start, message: "number too large".to_string() })
+    }
+
+    fn expr(&mut self) -> Result<Expr, CompileError> {
+        let mut lhs = self.term()?;
+        loop {
+            if self.eat("+") {
+                lhs = Expr::Add(Box::new(lhs), Box::new(self.term()?));
+            } else if self.eat("-") {
+                lhs = Expr::Sub(Box::new(lhs), Box::new(self.term()?));
+            } else {
+                return Ok(lhs);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, CompileError> {
+        let mut lhs = self.factor()?;
+        while self.eat("*") {
+            lhs = Expr::Mul(Box::new(lhs), Box::new(self.factor()?));
+        }
+        Ok(lhs)
+    }
+
+    fn factor(&mut self) -> Result<Expr, CompileError> {
+        match self.peek() {
+            Some('-') => {
+                self.position += 1;
+                Ok(Expr::Neg(Box::new(self.factor()?)))
+            }
+            Some('(') => {
+                self.position += 1;
+                let inner = self.expr()?;
+                self.expect(")")?;
+                Ok(inner)
+            }
+            Some(c) if c.is_ascii_digit() => Ok(Expr::Const(self.number()?)),
+            Some(_) => Ok(Expr::Var(self.identifier()?)),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+}
+
+/// Runs a `Program` on the PlonkChip of `TestCircuit`.
+pub(crate) struct ProgramCircuit<F: Field> {
+    pub program: Program,
+    pub private_inputs: Vec<Value<F>>,
+}
+
+impl<F: Field> PublicInputs for ProgramCircuit<F> {
+    fn public_input_schema(&self) -> PublicInputSchema {
+        // `Program::parse` already rejects variables declared twice
+        PublicInputSchema::new(self.program.public.iter().cloned()).expect("public variables are unique")
+    }
+}
+
+impl<F: Field + PrimeField> Circuit<F> for ProgramCircuit<F> {
+    type Config = TestConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        ProgramCircuit {
+            program: self.program.clone(),
+            private_inputs: vec![Value::unknown(); self.private_inputs.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        TestCircuit::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), plonk::Error> {
+        if self.private_inputs.len() != self.program.private.len() {
+            return Err(plonk::Error::Synthesis);
+        }
+
+        // the public inputs go first, see `bind_public_inputs`
+        let public = config.plonk_chip.bind_named_public_inputs(&config, &mut layouter, &self.public_input_schema())?;
+        let mut vars = HashMap::new();
+        for name in &self.program.public {
+            vars.insert(name.clone(), public.get(name)?);
+        }
+        for (name, value) in self.program.private.iter().zip(&self.private_inputs) {
+            let cell = layouter.assign_region(
+                || "Private input",
+                |mut region| region.assign_advice(|| "Private input", config.a, 0, || *value),
+            )?;
+            vars.insert(name.clone(), cell);
+        }
+
+        // the whole program goes in one region
+        config.plonk_chip.batch(&config, &mut layouter, "program", |rows| {
+            for (lhs, rhs) in &self.program.assertions {
+                let lhs = lhs.compile(rows, &vars)?;
+                let rhs = rhs.compile(rows, &vars)?;
+                rows.assert_equal(&lhs, &rhs)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+    use crate::tests::verify;
+
+    const SOURCE: &str = "
+        pub x; pub y; pub out; priv z;
+        (x*y + z) * (x*y) == out;
+        y == z
+    ";
+
+    fn circuit(source: &str, private: &[u64]) -> ProgramCircuit<Fr> {
+        ProgramCircuit {
+            program: Program::parse(source).unwrap(),
+            private_inputs: private.iter().map(|v| Value::known(Fr::from(*v))).collect(),
+        }
+    }
+
+    #[test]
+    fn test_should_parse_program(){
+        let program = Program::parse(SOURCE).unwrap();
+        assert_eq!(program.public, vec!["x", "y", "out"]);
+        assert_eq!(program.private, vec!["z"]);
+        assert_eq!(program.assertions.len(), 2);
+        assert_eq!(program.assertions[1], (Expr::Var("y".to_string()), Expr::Var("z".to_string())));
+    }
+
+    #[test]
+    fn test_should_report_errors(){
+        assert_eq!(Program::parse("pub x; x == y"), Err(CompileError::UndeclaredVariable("y".to_string())));
+        assert_eq!(Program::parse("pub x; priv x"), Err(CompileError::DuplicateVariable("x".to_string())));
+        assert!(matches!(Program::parse("pub x; x = 1"), Err(CompileError::Parse { position: 9, .. })));
+        assert!(matches!(Program::parse("pub x x == 1"), Err(CompileError::Parse { .. })));
+        assert!(matches!(Program::parse("pub pub"), Err(CompileError::Parse { .. })));
+    }
+
+    #[test]
+    fn test_should_prove_same_statement_as_hand_wired_circuit(){
+        // x = 1, y = z = 2: (1*2 + 2) * (1*2) = 8
+        assert!(verify(8, &circuit(SOURCE, &[2]), &[1, 2, 8].map(Fr::from)).is_ok());
+        assert!(verify(8, &circuit(SOURCE, &[2]), &[1, 2, 9].map(Fr::from)).is_err());
+        // y == z doesn't hold
+        assert!(verify(8, &circuit(SOURCE, &[3]), &[1, 2, 10].map(Fr::from)).is_err());
+    }
+
+    #[test]
+    fn test_should_support_constants_and_negation(){
+        // x^2 - 3x + 2 == 0 has the roots 1 and 2
+        let source = "priv x; x*x - 3*x + 2 == 0; -x == -(x)";
+        assert!(verify(8, &circuit(source, &[1]), &[]).is_ok());
+        assert!(verify(8, &circuit(source, &[2]), &[]).is_ok());
+        assert!(verify(8, &circuit(source, &[3]), &[]).is_err());
+    }
+}
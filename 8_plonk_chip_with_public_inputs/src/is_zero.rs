@@ -0,0 +1,210 @@
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    plonk::{self, Circuit, ConstraintSystem},
+};
+
+use ff::{Field, PrimeField};
+
+use crate::boolean::AssignedBool;
+use crate::public_inputs::{PublicInputSchema, PublicInputs};
+use crate::{PlonkChip, PlonkInstructions, TestCircuit, TestConfig};
+
+/// (witness cell, product cell)
+type WitnessProduct<F> = (AssignedCell<F, F>, AssignedCell<F, F>);
+
+impl<F: Field> PlonkChip<F> {
+    /// `x * witness * qm + qo * c + qc = 0`, where `witness` is assigned in `b` instead of being
+    /// copied and `c` holds `x * witness`. Returns the witness and `c`.
+    fn multiply_by_witness(
+        &self,
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+        witness: Value<F>,
+        qo: F,
+        qc: F,
+    ) -> Result<WitnessProduct<F>, plonk::Error> {
+        layouter.assign_region(
+            || "multiplication by witness",
+            |mut region| {
+                Self::_assign_plonk_regions(&mut region, config, F::ZERO, F::ZERO, F::ONE, qo, qc)?;
+
+                let a = x.copy_advice(|| "Copy a", &mut region, config.a, 0)?;
+                let b = region.assign_advice(|| "Witness", config.b, 0, || witness)?;
+                let c_value = a.value().cloned() * b.value().cloned();
+                let c = region.assign_advice(|| "Result", config.c, 0, || c_value)?;
+                Ok((b, c))
+            },
+        )
+    }
+
+    /// 1 if `x` is 0, else 0:
+    ///
+    /// `out = 1 - x * inv` and `x * out = 0`, where the prover gives `inv = 1 / x` (or anything
+    /// if `x` is 0). If `x` isn't 0 the second constraint forces `out = 0`, so `inv` must be its
+    /// inverse; if it is 0 the first one forces `out = 1`.
+    pub(crate) fn is_zero(
+        &self,
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        x: AssignedCell<F, F>,
+    ) -> Result<AssignedBool<F>, plonk::Error> {
+        let inv = x.value().map(|x| x.invert().unwrap_or(F::ZERO));
+        let (_, product) = self.multiply_by_witness(config, layouter, &x, inv, -F::ONE, F::ZERO)?;
+
+        let out = layouter.assign_region(
+            || "is zero",
+            |mut region| {
+                Self::_assign_plonk_regions(&mut region, config, -F::ONE, F::ZERO, F::ZERO, -F::ONE, F::ONE)?;
+
+                let a = product.copy_advice(|| "Copy a", &mut region, config.a, 0)?;
+                let c_value = a.value().map(|product| F::ONE - product);
+                region.assign_advice(|| "Result", config.c, 0, || c_value)
+            },
+        )?;
+
+        layouter.assign_region(
+            || "is zero: x * out = 0",
+            |mut region| {
+                Self::_assign_plonk_regions(&mut region, config, F::ZERO, F::ZERO, F::ONE, F::ZERO, F::ZERO)?;
+
+                x.copy_advice(|| "Copy a", &mut region, config.a, 0)?;
+                out.copy_advice(|| "Copy b", &mut region, config.b, 0)?;
+                Ok(())
+            },
+        )?;
+        Ok(AssignedBool(out))
+    }
+
+    pub(crate) fn is_equal(
+        &self,
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        lhs: AssignedCell<F, F>,
+        rhs: AssignedCell<F, F>,
+    ) -> Result<AssignedBool<F>, plonk::Error> {
+        let difference = self.subtract_cells(config, layouter, lhs, rhs)?;
+        self.is_zero(config, layouter, difference)
+    }
+
+    /// `x * inv - 1 = 0`. Fails with `Error::Synthesis` if `x` is known to be 0, as no witness
+    /// would satisfy the constraint.
+    pub(crate) fn inverse(
+        &self,
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        x: AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+        x.value().error_if_known_and(|x| x.is_zero_vartime())?;
+        let inv = x.value().map(|x| x.invert().unwrap());
+        let (inv, _) = self.multiply_by_witness(config, layouter, &x, inv, F::ZERO, -F::ONE)?;
+        Ok(inv)
+    }
+
+    /// `numerator * (1 / denominator)`, so a zero denominator can't be proven with any quotient.
+    pub(crate) fn divide(
+        &self,
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        numerator: AssignedCell<F, F>,
+        denominator: AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+        let inv = self.inverse(config, layouter, denominator)?;
+        self.multiply_cells(config, layouter, numerator, inv)
+    }
+}
+
+/// Public inputs: `[x == 0, x == y, y / x]` for private `x` and `y`. Synthesis fails if `x` is 0,
+/// see `without_division`.
+pub(crate) struct FieldOpsCircuit<F: Field> {
+    pub x: Value<F>,
+    pub y: Value<F>,
+    /// leaves `y / x` out, and the third public input with it
+    pub without_division: bool,
+}
+
+impl<F: Field> PublicInputs for FieldOpsCircuit<F> {
+    fn public_input_schema(&self) -> PublicInputSchema {
+        let names = ["x == 0", "x == y", "y / x"];
+        PublicInputSchema::new(names[..if self.without_division { 2 } else { 3 }].iter().copied()).unwrap()
+    }
+}
+
+impl<F: Field + PrimeField> Circuit<F> for FieldOpsCircuit<F> {
+    type Config = TestConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        FieldOpsCircuit { x: Value::unknown(), y: Value::unknown(), without_division: self.without_division }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        TestCircuit::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), plonk::Error> {
+        let chip = &config.plonk_chip;
+        let expected = chip.bind_named_public_inputs(&config, &mut layouter, &self.public_input_schema())?;
+
+        let [x, y] = [self.x, self.y].map(|value| {
+            layouter.assign_region(
+                || "Private input",
+                |mut region| region.assign_advice(|| "Private input", config.a, 0, || value),
+            )
+        });
+        let (x, y) = (x?, y?);
+
+        let mut results = vec![
+            ("x == 0", chip.is_zero(&config, &mut layouter, x.clone())?.cell()),
+            ("x == y", chip.is_equal(&config, &mut layouter, x.clone(), y.clone())?.cell()),
+        ];
+        if !self.without_division {
+            results.push(("y / x", chip.divide(&config, &mut layouter, y, x)?));
+        }
+        for (name, result) in results {
+            chip.enforce_cells_to_be_equal(&config, &mut layouter, result, expected.get(name)?)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+    use crate::tests::verify;
+
+    fn circuit(x: u64, y: u64, without_division: bool) -> FieldOpsCircuit<Fr> {
+        FieldOpsCircuit { x: Value::known(Fr::from(x)), y: Value::known(Fr::from(y)), without_division }
+    }
+
+    #[test]
+    fn test_should_compare_and_divide(){
+        assert!(verify(8, &circuit(4, 10, false), &[Fr::from(0), Fr::from(0), Fr::from(10) * Fr::from(4).invert().unwrap()]).is_ok());
+        assert!(verify(8, &circuit(3, 3, false), &[Fr::from(0), Fr::from(1), Fr::from(1)]).is_ok());
+        assert!(verify(8, &circuit(0, 0, true), &[Fr::from(1), Fr::from(1)]).is_ok());
+        assert!(verify(8, &circuit(0, 7, true), &[Fr::from(1), Fr::from(0)]).is_ok());
+    }
+
+    #[test]
+    fn test_should_not_accept_wrong_results(){
+        assert!(verify(8, &circuit(4, 10, false), &[Fr::from(1), Fr::from(0), Fr::from(10) * Fr::from(4).invert().unwrap()]).is_err());
+        assert!(verify(8, &circuit(3, 3, false), &[Fr::from(0), Fr::from(0), Fr::from(1)]).is_err());
+        assert!(verify(8, &circuit(4, 10, false), &[Fr::from(0), Fr::from(0), Fr::from(2)]).is_err());
+        assert!(verify(8, &circuit(0, 7, true), &[Fr::from(0), Fr::from(0)]).is_err());
+    }
+
+    #[test]
+    fn test_should_fail_on_division_by_zero(){
+        let circuit = circuit(0, 7, false);
+        let result = MockProver::run(8, &circuit, vec![vec![Fr::from(1), Fr::from(0), Fr::from(0)]]);
+        assert!(matches!(result, Err(plonk::Error::Synthesis)));
+    }
+}
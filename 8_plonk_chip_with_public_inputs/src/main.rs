@@ -1,3 +1,16 @@
+mod batch;
+mod boolean;
+mod circom;
+mod compiler;
+mod is_zero;
+mod membership;
+mod public_inputs;
+mod range;
+mod trace;
+mod turbo;
+
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::convert::TryInto;
 use std::marker::PhantomData;
 
@@ -12,10 +25,32 @@ use halo2_proofs::circuit::{AssignedCell, Region, Value};
 use halo2_proofs::plonk::{Advice, Column, Fixed, Instance};
 use halo2_proofs::poly::Rotation;
 
+use crate::public_inputs::{PublicInputCells, PublicInputSchema, PublicInputs};
+use crate::trace::{Operation, Trace};
+
 struct TestCircuit<F: Field> {
     _ph: PhantomData<F>,
-    public_inputs: [Value<F>; 3],
-    private_inputs: [Value<F>; 1],
+    params: TestParams,
+    private_inputs: Vec<Value<F>>,
+    /// records the operations of the chip, see `PlonkChip::recording`
+    trace: Option<Trace<F>>,
+}
+
+/// The inputs of `TestCircuit`: the public ones by name, in instance order, and how many private
+/// ones it takes. They are circuit params, which `without_witnesses` keeps, so the layout never
+/// depends on the witnesses. They don't change the columns, only the regions `synthesize` lays
+/// out, so `TestConfig` doesn't carry them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct TestParams {
+    public: PublicInputSchema,
+    private: usize,
+}
+
+/// The inputs of the program in `TestCircuit::synthesize`.
+impl Default for TestParams {
+    fn default() -> Self {
+        TestParams { public: PublicInputSchema::new(["x", "y", "expected_result"]).unwrap(), private: 1 }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -36,6 +71,8 @@ struct PlonkChip<F> {
     qm: Column<Fixed>,
     qo: Column<Fixed>,
     qc: Column<Fixed>,
+    q_pi: Column<Fixed>,
+    trace: Option<Trace<F>>,
 }
 
 impl<F: Field> PlonkChip<F> {
@@ -51,6 +88,7 @@ impl<F: Field> PlonkChip<F> {
         let qm = meta.fixed_column();
         let qo = meta.fixed_column();
         let qc = meta.fixed_column();
+        let q_pi = meta.fixed_column();
 
         meta.create_gate("Plonk Gate", |meta| {
             let pi_ = meta.query_instance(pi, Rotation::cur());
@@ -63,122 +101,225 @@ impl<F: Field> PlonkChip<F> {
             let qm_ = meta.query_fixed(qm, Rotation::cur());
             let qo_ = meta.query_fixed(qo, Rotation::cur());
             let qc_ = meta.query_fixed(qc, Rotation::cur());
+            let q_pi_ = meta.query_fixed(q_pi, Rotation::cur());
 
-            vec![a_.clone() * ql_ + b_.clone() * qr_ + a_ * b_ * qm_ + qo_ * c_ + qc_]
+            vec![a_.clone() * ql_ + b_.clone() * qr_ + a_ * b_ * qm_ + qo_ * c_ + qc_ + q_pi_ * pi_]
         });
 
-        Self { _ph: PhantomData, ql, qr, qm, qo, qc }
+        Self { _ph: PhantomData, ql, qr, qm, qo, qc, q_pi, trace: None }
+    }
+
+    /// Loads the first `count` public inputs, row `i` holding `a - pi = 0` for public input `i`.
+    ///
+    /// The gate reads the instance column at the same row, so this region must be the first one
+    /// of the circuit: the floor planner places it at row 0. No copy constraint to the instance
+    /// column is needed. Any other region before it, or a second call, fails with
+    /// `Error::Synthesis`, as its rows would no longer line up with the instance rows.
+    fn bind_public_inputs(
+        &self,
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        count: usize,
+    ) -> Result<Vec<AssignedCell<F, F>>, plonk::Error> {
+        let cells: Vec<_> = layouter.assign_region(
+            || "public inputs",
+            |mut region| {
+                (0..count)
+                    .map(|row| {
+                        region.assign_fixed(|| "Ql", self.ql, row, || Value::known(F::ONE))?;
+                        region.assign_fixed(|| "Qpi", self.q_pi, row, || Value::known(-F::ONE))?;
+                        let value = region.instance_value(config.pi, row)?;
+                        region.assign_advice(|| "Public input", config.a, row, || value)
+                    })
+                    .collect()
+            },
+        )?;
+        // `SimpleFloorPlanner` lays out the first region at row 0 of every column
+        if cells.first().is_some_and(|cell| *cell.cell().region_index != 0) {
+            return Err(plonk::Error::Synthesis);
+        }
+        for (row, cell) in cells.iter().enumerate() {
+            self.record(Operation::Public(row), &[], Some(cell));
+        }
+        Ok(cells)
+    }
+
+    fn _assign_plonk_regions(region: &mut Region<F>, config: &TestConfig<F>,
+        ql: F, qr: F, qm: F, qo: F, qc: F) -> Result<(), plonk::Error> {
+        Self::_assign_plonk_row(region, config, 0, [ql, qr, qm, qo, qc])
     }
 
-    fn constrain_advice_to_equal_public_input(&self,
-                                              config: &TestConfig<F>,
-                                              layouter: &mut impl Layouter<F>,
-                                              public_input_index: usize,
-                                              cell_to_constrain: AssignedCell<F,F>){
-        let _ = layouter.constrain_instance(cell_to_constrain.cell(), config.pi, public_input_index);
+    /// `[ql, qr, qm, qo, qc]` at `row` of the region.
+    fn _assign_plonk_row(region: &mut Region<F>, config: &TestConfig<F>,
+        row: usize, [ql, qr, qm, qo, qc]: [F; 5]) -> Result<(), plonk::Error> {
+        region.assign_fixed(|| "Ql", config.plonk_chip.ql, row, || Value::known(ql))?;
+        region.assign_fixed(|| "Qr", config.plonk_chip.qr, row, || Value::known(qr))?;
+        region.assign_fixed(|| "Qm", config.plonk_chip.qm, row, || Value::known(qm))?;
+        region.assign_fixed(|| "Qo", config.plonk_chip.qo, row, || Value::known(qo))?;
+        region.assign_fixed(|| "Qc", config.plonk_chip.qc, row, || Value::known(qc))?;
+        Ok(())
     }
+}
+
+/// The rows shared by `PlonkChip` and `TurboPlonkChip`, so a circuit can be written once for
+/// either of them. `Config` holds the advice columns the chip writes to.
+trait PlonkInstructions<F: Field> {
+    type Config;
 
     fn multiply_cells(
         &self,
-        config: &TestConfig<F>,
+        config: &Self::Config,
+        layouter: &mut impl Layouter<F>,
+        lhs: AssignedCell<F, F>,
+        rhs: AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, plonk::Error>;
+
+    fn add_cells(
+        &self,
+        config: &Self::Config,
         layouter: &mut impl Layouter<F>,
         lhs: AssignedCell<F, F>,
         rhs: AssignedCell<F, F>,
-    ) -> Option<AssignedCell<F, F>> {
-        let mut result_cell = None;
-        let _ = layouter.assign_region(
+    ) -> Result<AssignedCell<F, F>, plonk::Error>;
+
+    fn subtract_cells(
+        &self,
+        config: &Self::Config,
+        layouter: &mut impl Layouter<F>,
+        lhs: AssignedCell<F, F>,
+        rhs: AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, plonk::Error>;
+
+    fn new_constant_cell(
+        &self,
+        config: &Self::Config,
+        layouter: &mut impl Layouter<F>,
+        constant_value: F,
+    ) -> Result<AssignedCell<F, F>, plonk::Error>;
+
+    fn enforce_cells_to_be_equal(
+        &self,
+        config: &Self::Config,
+        layouter: &mut impl Layouter<F>,
+        lhs: AssignedCell<F, F>,
+        rhs: AssignedCell<F, F>,
+    ) -> Result<(), plonk::Error>;
+}
+
+impl<F: Field> PlonkInstructions<F> for PlonkChip<F> {
+    type Config = TestConfig<F>;
+
+    fn multiply_cells(
+        &self,
+        config: &Self::Config,
+        layouter: &mut impl Layouter<F>,
+        lhs: AssignedCell<F, F>,
+        rhs: AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+        let out = layouter.assign_region(
             || "multiplication",
             |mut region| {
-                Self::_assign_plonk_regions(&mut region, config, F::ZERO, F::ZERO, F::ONE, -F::ONE, F::ZERO);
+                Self::_assign_plonk_regions(&mut region, config, F::ZERO, F::ZERO, F::ONE, -F::ONE, F::ZERO)?;
 
                 let a = lhs.copy_advice(|| "Copy a", &mut region, config.a, 0)?;
                 let b = rhs.copy_advice(|| "Copy b", &mut region, config.b, 0)?;
                 let c_value = a.value().cloned() * b.value().cloned();
-                let c = region.assign_advice(|| "Result", config.c, 0, || c_value)?;
-
-                result_cell = Some(c);
-                Ok(())
+                region.assign_advice(|| "Result", config.c, 0, || c_value)
             },
-        );
-        result_cell
+        )?;
+        self.record(Operation::Multiply, &[&lhs, &rhs], Some(&out));
+        Ok(out)
     }
 
     fn add_cells(
         &self,
-        config: &TestConfig<F>,
+        config: &Self::Config,
         layouter: &mut impl Layouter<F>,
         lhs: AssignedCell<F, F>,
         rhs: AssignedCell<F, F>,
-    ) -> Option<AssignedCell<F, F>> {
-        let mut result_cell = None;
-        let _ = layouter.assign_region(
+    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+        let out = layouter.assign_region(
             || "addition",
             |mut region| {
-                Self::_assign_plonk_regions(&mut region, config, F::ONE, F::ONE, F::ZERO, -F::ONE, F::ZERO);
+                Self::_assign_plonk_regions(&mut region, config, F::ONE, F::ONE, F::ZERO, -F::ONE, F::ZERO)?;
 
                 let a = lhs.copy_advice(|| "Copy a", &mut region, config.a, 0)?;
                 let b = rhs.copy_advice(|| "Copy b", &mut region, config.b, 0)?;
                 let c_value = a.value().cloned() + b.value().cloned();
-                let c = region.assign_advice(|| "Result", config.c, 0, || c_value)?;
+                region.assign_advice(|| "Result", config.c, 0, || c_value)
+            },
+        )?;
+        self.record(Operation::Add, &[&lhs, &rhs], Some(&out));
+        Ok(out)
+    }
 
-                result_cell = Some(c);
-                Ok(())
+    fn subtract_cells(
+        &self,
+        config: &Self::Config,
+        layouter: &mut impl Layouter<F>,
+        lhs: AssignedCell<F, F>,
+        rhs: AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+        let out = layouter.assign_region(
+            || "subtraction",
+            |mut region| {
+                Self::_assign_plonk_regions(&mut region, config, F::ONE, -F::ONE, F::ZERO, -F::ONE, F::ZERO)?;
+
+                let a = lhs.copy_advice(|| "Copy a", &mut region, config.a, 0)?;
+                let b = rhs.copy_advice(|| "Copy b", &mut region, config.b, 0)?;
+                let c_value = a.value().cloned() - b.value().cloned();
+                region.assign_advice(|| "Result", config.c, 0, || c_value)
             },
-        );
-        result_cell
+        )?;
+        self.record(Operation::Subtract, &[&lhs, &rhs], Some(&out));
+        Ok(out)
     }
 
     fn new_constant_cell(
         &self,
-        config: &TestConfig<F>,
+        config: &Self::Config,
         layouter: &mut impl Layouter<F>,
         constant_value: F,
-    ) -> Option<AssignedCell<F, F>> {
-        let mut result_cell = None;
-        let _ = layouter.assign_region(
+    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+        let out = layouter.assign_region(
             || "constant",
             |mut region| {
-                Self::_assign_plonk_regions(&mut region, config, F::ZERO, F::ZERO, F::ZERO, -F::ONE, constant_value);
+                Self::_assign_plonk_regions(&mut region, config, F::ZERO, F::ZERO, F::ZERO, -F::ONE, constant_value)?;
 
-                let c = region.assign_advice(|| "Result", config.c,
-                                             0, || Value::known(constant_value))?;
-                result_cell = Some(c);
-                Ok(())
+                region.assign_advice(|| "Result", config.c,
+                                     0, || Value::known(constant_value))
             },
-        );
-        result_cell
+        )?;
+        self.record(Operation::Constant, &[], Some(&out));
+        Ok(out)
     }
 
     fn enforce_cells_to_be_equal(
         &self,
-        config: &TestConfig<F>,
+        config: &Self::Config,
         layouter: &mut impl Layouter<F>,
         lhs: AssignedCell<F, F>,
         rhs: AssignedCell<F, F>,
-    ) {
-        let _ = layouter.assign_region(
+    ) -> Result<(), plonk::Error> {
+        layouter.assign_region(
             || "addition",
             |mut region| {
-                Self::_assign_plonk_regions(&mut region, config, F::ONE, -F::ONE, F::ZERO, F::ZERO, F::ZERO);
+                Self::_assign_plonk_regions(&mut region, config, F::ONE, -F::ONE, F::ZERO, F::ZERO, F::ZERO)?;
 
                 let _a = lhs.copy_advice(|| "Copy a", &mut region, config.a, 0)?;
                 let _b = rhs.copy_advice(|| "Copy b", &mut region, config.b, 0)?;
 
                 Ok(())
             },
-        );
-    }
-
-    fn _assign_plonk_regions(region: &mut Region<F>, config: &TestConfig<F>,
-        ql: F, qr: F, qm: F, qo: F, qc: F) {
-        let _ql = region.assign_fixed(|| "Ql", config.plonk_chip.ql, 0, || Value::known(ql));
-        let _qr = region.assign_fixed(|| "Qr", config.plonk_chip.qr, 0, || Value::known(qr));
-        let _qm = region.assign_fixed(|| "Qm", config.plonk_chip.qm, 0, || Value::known(qm));
-        let _qo = region.assign_fixed(|| "Qo", config.plonk_chip.qo, 0, || Value::known(qo));
-        let _qc = region.assign_fixed(|| "Qc", config.plonk_chip.qc, 0, || Value::known(qc));
+        )?;
+        self.record(Operation::Equal, &[&lhs, &rhs], None);
+        Ok(())
     }
 }
 
+/// (public input cells, private input cells)
+type InputCells<F> = (PublicInputCells<F>, Vec<AssignedCell<F, F>>);
+
 impl<F: Field + PrimeField> TestCircuit<F> {
     fn unconstrained(
         &self,
@@ -194,32 +335,49 @@ impl<F: Field + PrimeField> TestCircuit<F> {
 
     fn register_inputs(&self,
                        config: &<TestCircuit<F> as Circuit<F>>::Config,
-                       layouter: &mut impl Layouter<F>) -> (Vec<AssignedCell<F,F>>, Vec<AssignedCell<F,F>>){
-        let mut public_input_cells = vec![];
-        let mut private_input_cells = vec![];
-        for value in self.public_inputs {
-            public_input_cells.push(self.unconstrained(&config, layouter, value).unwrap());
+                       layouter: &mut impl Layouter<F>) -> Result<InputCells<F>, plonk::Error> {
+        if self.private_inputs.len() != self.params.private {
+            return Err(plonk::Error::Synthesis);
         }
-        for value in self.private_inputs {
-            private_input_cells.push(self.unconstrained(&config, layouter, value).unwrap());
+        let public_input_cells = config.plonk_chip.bind_named_public_inputs(config, layouter, &self.params.public)?;
+        let mut private_input_cells = vec![];
+        for value in self.private_inputs.iter().copied() {
+            private_input_cells.push(self.unconstrained(config, layouter, value)?);
         }
-        (public_input_cells, private_input_cells)
+        Ok((public_input_cells, private_input_cells))
+    }
+}
+
+impl<F: Field> PublicInputs for TestCircuit<F> {
+    fn public_input_schema(&self) -> PublicInputSchema {
+        self.params.public.clone()
     }
 }
 
 impl<F: Field + PrimeField> Circuit<F> for TestCircuit<F> {
     type Config = TestConfig<F>;
     type FloorPlanner = SimpleFloorPlanner;
+    type Params = TestParams;
 
     fn without_witnesses(&self) -> Self {
         TestCircuit {
             _ph: PhantomData,
-            public_inputs: [Value::unknown(); 3],
-            private_inputs: [Value::unknown(); 1],
+            params: self.params.clone(),
+            private_inputs: vec![Value::unknown(); self.private_inputs.len()],
+            trace: None,
         }
     }
 
+    fn params(&self) -> Self::Params {
+        self.params.clone()
+    }
+
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        Self::configure_with_params(meta, TestParams::default())
+    }
+
+    /// The columns are the same for any params, `register_inputs` reads them from `self`.
+    fn configure_with_params(meta: &mut ConstraintSystem<F>, _params: Self::Params) -> Self::Config {
         let a = meta.advice_column();
         let b = meta.advice_column();
         let c = meta.advice_column();
@@ -241,54 +399,225 @@ impl<F: Field + PrimeField> Circuit<F> for TestCircuit<F> {
         mut config: Self::Config,
         mut layouter: impl Layouter<F>,
     ) -> Result<(), plonk::Error> {
+        if let Some(trace) = &self.trace {
+            config.plonk_chip = config.plonk_chip.recording(trace);
+        }
+
         // Aplica para cualquier programa
         let (public_input_cells, private_input_cells) =
-            self.register_inputs(&mut config, &mut layouter);
+            self.register_inputs(&config, &mut layouter)?;
 
         // Aplica para el programa específico
 
-        // public_inputs = [x,y,expected_result]
-        // private_inputs = [z]
-        let x = public_input_cells[0].clone();
-        let y = public_input_cells[1].clone();
-        let expected_result = public_input_cells[2].clone();
-        let z = private_input_cells[0].clone();
-
-        // aux1 == x*y
-        let aux1 = config.plonk_chip.multiply_cells(&config, &mut layouter, x.clone(), y.clone()).unwrap();
-        // aux2 == aux1 + z
-        let aux2 = config.plonk_chip.add_cells(&config, &mut layouter, aux1.clone(), z.clone()).unwrap();
-        // aux3 == aux1 * aux2
-        let aux3 = config.plonk_chip.multiply_cells(&config, &mut layouter, aux1, aux2).unwrap();
-        // y == z
-        config.plonk_chip.enforce_cells_to_be_equal(&config, &mut layouter, y.clone(), z);
-
-        // aux3 == expected_result
-        config.plonk_chip.enforce_cells_to_be_equal(&config, &mut layouter, aux3, expected_result.clone());
-
-        // Enforce public inputs
-        for (i, cell) in [x,y,expected_result].into_iter().enumerate() {
-            layouter.constrain_instance(cell.cell(), config.pi, i)?;
-        }
-
-        Ok(())
+        // private_inputs = [z, ...]
+        let x = public_input_cells.get("x")?;
+        let y = public_input_cells.get("y")?;
+        let expected_result = public_input_cells.get("expected_result")?;
+        let z = private_input_cells.first().cloned().ok_or(plonk::Error::Synthesis)?;
+
+        config.plonk_chip.batch(&config, &mut layouter, "program", |rows| {
+            // aux1 == x*y
+            let aux1 = rows.multiply(&x, &y)?;
+            // aux2 == aux1 + z
+            let aux2 = rows.add(&aux1, &z)?;
+            // aux3 == aux1 * aux2
+            let aux3 = rows.multiply(&aux1, &aux2)?;
+            // y == z
+            rows.assert_equal(&y, &z)?;
+
+            // aux3 == expected_result
+            rows.assert_equal(&aux3, &expected_result)
+        })
     }
 }
 
 fn main() {
     use halo2_proofs::halo2curves::bn256::Fr;
 
-    let public_input_values = vec![Fr::from(1),Fr::from(2), Fr::from(8)];
     let private_input_values = vec![Fr::from(2)];
     let circuit = TestCircuit::<Fr> {
         _ph: PhantomData,
-        public_inputs: [
-            Value::known(public_input_values[0]),
-            Value::known(public_input_values[1]),
-            Value::known(public_input_values[2]),
-        ],
-        private_inputs: [Value::known(private_input_values[0])],
+        params: TestParams::default(),
+        private_inputs: private_input_values.iter().map(|v| Value::known(*v)).collect(),
+        trace: Some(Trace::default()),
+    };
+    let schema = circuit.public_input_schema();
+    let public_input_values = HashMap::from([("x", Fr::from(1)), ("y", Fr::from(2)), ("expected_result", Fr::from(8))]);
+    let instance = schema.instance(&public_input_values).unwrap_or_else(|e| panic!("{}", e));
+    let prover = MockProver::run(8, &circuit, vec![instance]).unwrap();
+    prover.verify().unwrap();
+    // the aux1/aux2/aux3 wiring; `cargo run -- --dot | dot -Tsvg` draws it instead
+    let trace = circuit.trace.as_ref().unwrap();
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("--dot") {
+        print!("{}", trace.to_dot());
+        return;
+    }
+    print!("public inputs of TestCircuit:\n{}", schema);
+    print!("TestCircuit:\n{}", trace.to_text());
+
+    // the secret is one of the public inputs, without saying which one
+    let set = vec![Fr::from(3), Fr::from(5), Fr::from(8), Fr::from(13)];
+    let circuit = membership::MembershipCircuit::<Fr> {
+        secret: Value::known(Fr::from(8)),
+        set_size: set.len(),
+    };
+    let prover = MockProver::run(8, &circuit, vec![set]).unwrap();
+    prover.verify().unwrap();
+
+    // the same statement as TestCircuit, compiled from text
+    let program = compiler::Program::parse(
+        "pub x; pub y; pub out; priv z;
+         (x*y + z) * (x*y) == out;
+         y == z",
+    )
+    .unwrap();
+    let circuit = compiler::ProgramCircuit::<Fr> {
+        program,
+        private_inputs: vec![Value::known(Fr::from(2))],
+    };
+    let prover = MockProver::run(8, &circuit, vec![vec![Fr::from(1), Fr::from(2), Fr::from(8)]]).unwrap();
+    prover.verify().unwrap();
+
+    // a and b, a or b, a xor b, not a, a ? 10 : 20
+    let circuit = boolean::BooleanCircuit::<Fr> { a: Value::known(Fr::from(1)), b: Value::known(Fr::from(0)) };
+    let public_inputs = [0, 1, 1, 0, 10].map(Fr::from).to_vec();
+    let prover = MockProver::run(8, &circuit, vec![public_inputs]).unwrap();
+    prover.verify().unwrap();
+
+    // x == 0, x == y, y / x
+    let circuit = is_zero::FieldOpsCircuit::<Fr> {
+        x: Value::known(Fr::from(2)),
+        y: Value::known(Fr::from(8)),
+        without_division: false,
     };
-    let prover = MockProver::run(8, &circuit, vec![public_input_values]).unwrap();
+    let public_inputs = vec![Fr::from(0), Fr::from(0), Fr::from(4)];
+    let prover = MockProver::run(8, &circuit, vec![public_inputs]).unwrap();
     prover.verify().unwrap();
+
+    // an age check: 17 < 18, max and min, with 7-bit values
+    let circuit = range::ComparisonCircuit::<Fr> { a: Value::known(Fr::from(17)), b: Value::known(Fr::from(18)), bits: 7 };
+    let public_inputs = vec![Fr::from(1), Fr::from(18), Fr::from(17)];
+    let prover = MockProver::run(9, &circuit, vec![public_inputs]).unwrap();
+    prover.verify().unwrap();
+
+    // sums take fewer rows with a fourth wire and the next-row term
+    let terms: Vec<u64> = (1..=10).collect();
+    let circuit = turbo::TurboCircuit::<Fr, true> {
+        terms: terms.iter().map(|t| Value::known(Fr::from(*t))).collect(),
+        sum: Fr::from(terms.iter().sum::<u64>()),
+        product: Fr::from(10),
+        sum_rows: Cell::new(0),
+    };
+    let prover = MockProver::run(8, &circuit, vec![]).unwrap();
+    prover.verify().unwrap();
+
+    // 3^5 with its four multiplications in a single region
+    let circuit = batch::PowerCircuit::<Fr> { x: Value::known(Fr::from(3)), exponent: 5, batched: true };
+    let prover = MockProver::run(8, &circuit, vec![vec![Fr::from(243)]]).unwrap();
+    prover.verify().unwrap();
+
+    println!("rows to add n values: PlonkChip | width 4 | width 4 + next row");
+    for n in [4, 10, 100] {
+        println!("{:>4}: {:>8} | {:>7} | {:>18}", n, n - 1, turbo::measure::<false>(n), turbo::measure::<true>(n));
+    }
+
+    // a circuit written in circom: cargo run -- circuit.r1cs witness.wtns
+    if let [_, r1cs, witness] = args.as_slice() {
+        let r1cs = circom::load_r1cs::<Fr>(r1cs).unwrap_or_else(|e| panic!("{}", e));
+        let witness = circom::load_witness::<Fr>(witness, &r1cs).unwrap_or_else(|e| panic!("{}", e));
+        let public_inputs = circom::CircomCircuit::public_inputs(&witness, &r1cs).unwrap_or_else(|e| panic!("{}", e));
+        let circuit = circom::CircomCircuit::new(r1cs, &witness).unwrap_or_else(|e| panic!("{}", e));
+        let k = circuit.rows().next_power_of_two().trailing_zeros().max(4) + 1;
+        let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+        prover.verify().unwrap();
+        println!("circom circuit verified with k = {}", k);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use halo2_proofs::dev::VerifyFailure;
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+
+    /// Runs `circuit` with `public` as its instance column, for the tests of every module.
+    pub(crate) fn verify(k: u32, circuit: &impl Circuit<Fr>, public: &[Fr]) -> Result<(), Vec<VerifyFailure>> {
+        MockProver::run(k, circuit, vec![public.to_vec()]).unwrap().verify()
+    }
+
+    pub(crate) fn circuit(public: &[&str], private_inputs: &[u64]) -> TestCircuit<Fr> {
+        TestCircuit {
+            _ph: PhantomData,
+            params: TestParams { public: PublicInputSchema::new(public.iter().copied()).unwrap(), private: private_inputs.len() },
+            private_inputs: private_inputs.iter().map(|v| Value::known(Fr::from(*v))).collect(),
+            trace: None,
+        }
+    }
+
+    #[test]
+    fn test_should_take_inputs_of_any_length(){
+        // the instance follows the params: a public input ahead of the program's and a second
+        // private input, neither used by the program
+        let circuit = circuit(&["nonce", "x", "y", "expected_result"], &[2, 5]);
+        let values = HashMap::from([("x", 1), ("y", 2), ("expected_result", 8), ("nonce", 42)].map(|(k, v)| (k, Fr::from(v))));
+        let instance = circuit.public_input_schema().instance(&values).unwrap();
+        assert_eq!(instance[0], Fr::from(42));
+        MockProver::run(8, &circuit, vec![instance.clone()]).unwrap().verify().unwrap();
+
+        let mut wrong = instance;
+        wrong[3] = Fr::from(9);
+        assert!(MockProver::run(8, &circuit, vec![wrong]).unwrap().verify().is_err());
+    }
+
+    #[test]
+    fn test_should_fail_if_the_inputs_do_not_match_the_params(){
+        let mut too_many = circuit(&["x", "y", "expected_result"], &[2]);
+        too_many.private_inputs.push(Value::known(Fr::from(3)));
+        let instance = vec![Fr::from(1), Fr::from(2), Fr::from(8)];
+        assert!(matches!(MockProver::run(8, &too_many, vec![instance.clone()]), Err(plonk::Error::Synthesis)));
+
+        // the program needs `y`
+        let without_y = circuit(&["x", "expected_result"], &[2]);
+        assert!(matches!(MockProver::run(8, &without_y, vec![instance]), Err(plonk::Error::Synthesis)));
+    }
+
+    #[test]
+    fn test_should_bind_public_inputs_only_in_the_first_region(){
+        /// Binds `x` after a private input, or twice.
+        struct LateBinding {
+            twice: bool,
+        }
+
+        impl Circuit<Fr> for LateBinding {
+            type Config = TestConfig<Fr>;
+            type FloorPlanner = SimpleFloorPlanner;
+            type Params = ();
+
+            fn without_witnesses(&self) -> Self {
+                LateBinding { twice: self.twice }
+            }
+
+            fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+                TestCircuit::configure(meta)
+            }
+
+            fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fr>) -> Result<(), plonk::Error> {
+                let chip = &config.plonk_chip;
+                if self.twice {
+                    chip.bind_public_inputs(&config, &mut layouter, 1)?;
+                } else {
+                    circuit(&[], &[]).unconstrained(&config, &mut layouter, Value::known(Fr::from(5)))?;
+                }
+                chip.bind_public_inputs(&config, &mut layouter, 1)?;
+                Ok(())
+            }
+        }
+
+        for twice in [false, true] {
+            let result = MockProver::run(8, &LateBinding { twice }, vec![vec![Fr::from(5)]]);
+            assert!(matches!(result, Err(plonk::Error::Synthesis)));
+        }
+    }
 }
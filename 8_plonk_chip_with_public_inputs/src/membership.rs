@@ -0,0 +1,122 @@
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    plonk::{self, Circuit, ConstraintSystem},
+};
+
+use ff::{Field, PrimeField};
+
+use crate::public_inputs::{PublicInputSchema, PublicInputs};
+use crate::{PlonkChip, PlonkInstructions, TestCircuit, TestConfig};
+
+impl<F: Field> PlonkChip<F> {
+    /// `x` is one of the cells of `set`: `(x - p_0) * (x - p_1) * ... * (x - p_n) = 0`.
+    ///
+    /// The product is 0 whichever factor is 0, so the proof doesn't reveal which cell matched.
+    pub(crate) fn enforce_cell_in_set(
+        &self,
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        x: AssignedCell<F, F>,
+        set: &[AssignedCell<F, F>],
+    ) -> Result<(), plonk::Error> {
+        let mut product: Option<AssignedCell<F, F>> = None;
+        for p in set {
+            let difference = self.subtract_cells(config, layouter, x.clone(), p.clone())?;
+            product = Some(match product {
+                None => difference,
+                Some(product) => self.multiply_cells(config, layouter, product, difference)?,
+            });
+        }
+
+        // x can't be in an empty set
+        let product = product.ok_or(plonk::Error::Synthesis)?;
+        let zero = self.new_constant_cell(config, layouter, F::ZERO)?;
+        self.enforce_cells_to_be_equal(config, layouter, product, zero)
+    }
+}
+
+/// Proves that a secret is one of the public inputs, `member_0` to `member_{set_size - 1}`. The
+/// number of public inputs is part of the circuit, their values are not.
+pub(crate) struct MembershipCircuit<F: Field> {
+    pub secret: Value<F>,
+    pub set_size: usize,
+}
+
+impl<F: Field> PublicInputs for MembershipCircuit<F> {
+    fn public_input_schema(&self) -> PublicInputSchema {
+        PublicInputSchema::new((0..self.set_size).map(|i| format!("member_{}", i))).unwrap()
+    }
+}
+
+impl<F: Field + PrimeField> Circuit<F> for MembershipCircuit<F> {
+    type Config = TestConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        MembershipCircuit {
+            secret: Value::unknown(),
+            set_size: self.set_size,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        TestCircuit::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), plonk::Error> {
+        let set = config.plonk_chip.bind_named_public_inputs(&config, &mut layouter, &self.public_input_schema())?;
+        let secret = layouter.assign_region(
+            || "Secret",
+            |mut region| region.assign_advice(|| "Secret", config.a, 0, || self.secret),
+        )?;
+        config.plonk_chip.enforce_cell_in_set(&config, &mut layouter, secret, set.cells())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+    use crate::tests::verify;
+
+    /// The circuit and its instance, built from the names of the schema.
+    fn circuit_with_instance(secret: u64, set: &[u64]) -> (MembershipCircuit<Fr>, Vec<Fr>) {
+        let circuit = MembershipCircuit { secret: Value::known(Fr::from(secret)), set_size: set.len() };
+        let values: HashMap<String, Fr> = set.iter().enumerate().map(|(i, p)| (format!("member_{}", i), Fr::from(*p))).collect();
+        let instance = circuit.public_input_schema().instance(&values).unwrap();
+        (circuit, instance)
+    }
+
+    #[test]
+    fn test_should_accept_any_member(){
+        for secret in [3, 5, 8, 13] {
+            let (circuit, instance) = circuit_with_instance(secret, &[3, 5, 8, 13]);
+            assert!(verify(8, &circuit, &instance).is_ok());
+        }
+        let (circuit, instance) = circuit_with_instance(7, &[7]);
+        assert!(verify(8, &circuit, &instance).is_ok());
+    }
+
+    #[test]
+    fn test_should_not_accept_non_members(){
+        let (circuit, instance) = circuit_with_instance(4, &[3, 5, 8, 13]);
+        assert!(verify(8, &circuit, &instance).is_err());
+        let (circuit, instance) = circuit_with_instance(0, &[3, 5, 8, 13]);
+        assert!(verify(8, &circuit, &instance).is_err());
+    }
+
+    #[test]
+    fn test_should_reject_empty_set(){
+        let circuit = MembershipCircuit::<Fr> { secret: Value::known(Fr::from(1)), set_size: 0 };
+        assert!(MockProver::run(8, &circuit, vec![vec![]]).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter},
+    plonk,
+};
+
+use ff::Field;
+
+use crate::{PlonkChip, TestConfig};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PublicInputError {
+    /// declared twice in the same schema
+    Duplicate(String),
+    /// declared but without a value
+    Missing(String),
+    /// given a value but not declared
+    Unknown(String),
+}
+
+impl fmt::Display for PublicInputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PublicInputError::Duplicate(name) => write!(f, "public input `{}` is declared twice", name),
+            PublicInputError::Missing(name) => write!(f, "missing value for public input `{}`", name),
+            PublicInputError::Unknown(name) => write!(f, "`{}` is not a public input of this circuit", name),
+        }
+    }
+}
+
+/// The names of the public inputs of a circuit. Row `i` of the instance column holds the input
+/// declared `i`-th; nothing else needs to know the order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PublicInputSchema {
+    names: Vec<String>,
+}
+
+impl PublicInputSchema {
+    pub fn new<S: Into<String>>(names: impl IntoIterator<Item = S>) -> Result<Self, PublicInputError> {
+        let mut schema = PublicInputSchema { names: vec![] };
+        for name in names {
+            let name = name.into();
+            if schema.row(&name).is_some() {
+                return Err(PublicInputError::Duplicate(name));
+            }
+            schema.names.push(name);
+        }
+        Ok(schema)
+    }
+
+    /// The instance row of `name`.
+    pub fn row(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|declared| declared == name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// The instance column for `values`, which must have a value for every input and nothing else.
+    pub fn instance<K, F>(&self, values: &HashMap<K, F>) -> Result<Vec<F>, PublicInputError>
+    where
+        K: Borrow<str> + Hash + Eq,
+        F: Copy,
+    {
+        if let Some(name) = values.keys().find(|name| self.row((*name).borrow()).is_none()) {
+            return Err(PublicInputError::Unknown(name.borrow().to_string()));
+        }
+        self.names
+            .iter()
+            .map(|name| values.get(name.as_str()).copied().ok_or_else(|| PublicInputError::Missing(name.clone())))
+            .collect()
+    }
+}
+
+/// What each instance row means, for verifiers.
+impl fmt::Display for PublicInputSchema {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (row, name) in self.names.iter().enumerate() {
+            writeln!(f, "{:>4}: {}", row, name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Circuits whose public inputs are declared by name.
+pub(crate) trait PublicInputs {
+    fn public_input_schema(&self) -> PublicInputSchema;
+}
+
+/// The cells loaded by `PlonkChip::bind_named_public_inputs`.
+#[derive(Debug, Clone)]
+pub(crate) struct PublicInputCells<F: Field> {
+    schema: PublicInputSchema,
+    cells: Vec<AssignedCell<F, F>>,
+}
+
+impl<F: Field> PublicInputCells<F> {
+    /// Fails with `Error::Synthesis` if `name` isn't declared, which is a bug of the circuit.
+    pub fn get(&self, name: &str) -> Result<AssignedCell<F, F>, plonk::Error> {
+        let row = self.schema.row(name).ok_or(plonk::Error::Synthesis)?;
+        Ok(self.cells[row].clone())
+    }
+
+    /// Every cell, in instance order.
+    pub fn cells(&self) -> &[AssignedCell<F, F>] {
+        &self.cells
+    }
+}
+
+impl<F: Field> PlonkChip<F> {
+    /// `bind_public_inputs` for the inputs of `schema`, so it must be the first region as well.
+    pub(crate) fn bind_named_public_inputs(
+        &self,
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        schema: &PublicInputSchema,
+    ) -> Result<PublicInputCells<F>, plonk::Error> {
+        let cells = self.bind_public_inputs(config, layouter, schema.len())?;
+        Ok(PublicInputCells { schema: schema.clone(), cells })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::circuit::Value;
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+    use crate::boolean::BooleanCircuit;
+
+    #[test]
+    fn test_should_build_the_instance_in_declaration_order(){
+        let schema = PublicInputSchema::new(["x", "y", "out"]).unwrap();
+        let values = HashMap::from([("out", 8), ("x", 1), ("y", 2)]);
+        assert_eq!(schema.instance(&values), Ok(vec![1, 2, 8]));
+        assert_eq!(schema.to_string(), "   0: x\n   1: y\n   2: out\n");
+    }
+
+    #[test]
+    fn test_should_reject_wrong_names(){
+        assert_eq!(PublicInputSchema::new(["x", "x"]), Err(PublicInputError::Duplicate("x".to_string())));
+
+        let schema = PublicInputSchema::new(["x", "y"]).unwrap();
+        assert_eq!(schema.instance(&HashMap::from([("x", 1)])), Err(PublicInputError::Missing("y".to_string())));
+        let values = HashMap::from([("x", 1), ("y", 2), ("z", 3)]);
+        assert_eq!(schema.instance(&values), Err(PublicInputError::Unknown("z".to_string())));
+    }
+
+    #[test]
+    fn test_should_verify_with_an_instance_built_from_names(){
+        let circuit = BooleanCircuit::<Fr> { a: Value::known(Fr::from(1)), b: Value::known(Fr::from(0)) };
+        let schema = circuit.public_input_schema();
+        let values = HashMap::from([("select", 10), ("not", 0), ("xor", 1), ("or", 1), ("and", 0)].map(|(k, v)| (k, Fr::from(v))));
+        let instance = schema.instance(&values).unwrap();
+        MockProver::run(8, &circuit, vec![instance]).unwrap().verify().unwrap();
+    }
+}
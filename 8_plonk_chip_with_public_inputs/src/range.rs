@@ -0,0 +1,298 @@
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    plonk::{self, Advice, Circuit, Column, ConstraintSystem, Fixed, Selector, TableColumn},
+    poly::Rotation,
+};
+
+use ff::{Field, PrimeField};
+
+use crate::boolean::AssignedBool;
+use crate::public_inputs::{PublicInputSchema, PublicInputs};
+use crate::{PlonkChip, PlonkInstructions, TestCircuit, TestConfig};
+
+const LIMB_BITS: usize = 8;
+
+/// Range checks with limbs of `LIMB_BITS`, each looked up in `t_range` like the `u8` table of
+/// `9_bit_operations`. `x` is split from the least significant limb:
+///
+/// | limb | acc                                 | q_decompose | q_top | shift          |
+/// | l_0  | x = l_0 + 2^8 * acc_1               | 1           | 0     |                |
+/// | l_1  | acc_1 = l_1 + 2^8 * acc_2           | 1           | 0     |                |
+/// | ...  | ...                                 |             |       |                |
+/// | l_n  | acc_n = l_n                         | 0           | 1     | 2^(8 - bits%8) |
+///
+/// The top limb is also looked up times `shift`, which is below 2^8 only if the limb is below
+/// 2^(bits % 8).
+#[derive(Clone, Debug)]
+pub(crate) struct RangeCheckConfig {
+    q_range: Selector,
+    q_decompose: Selector,
+    q_top: Selector,
+    limb: Column<Advice>,
+    acc: Column<Advice>,
+    shift: Column<Fixed>,
+    t_range: TableColumn,
+}
+
+impl RangeCheckConfig {
+    /// `limb` and `acc` can be the advice columns of `PlonkChip`, as its coefficients are 0 in
+    /// these rows. `acc` needs equality enabled.
+    pub fn configure<F: PrimeField>(meta: &mut ConstraintSystem<F>, limb: Column<Advice>, acc: Column<Advice>) -> Self {
+        let q_range = meta.complex_selector();
+        let q_decompose = meta.selector();
+        let q_top = meta.complex_selector();
+        let shift = meta.fixed_column();
+        let t_range = meta.lookup_table_column();
+
+        meta.lookup("range check limb", |meta| {
+            let q_range = meta.query_selector(q_range);
+            let limb = meta.query_advice(limb, Rotation::cur());
+            vec![(q_range * limb, t_range)]
+        });
+        meta.lookup("range check shifted limb", |meta| {
+            let q_top = meta.query_selector(q_top);
+            let limb = meta.query_advice(limb, Rotation::cur());
+            let shift = meta.query_fixed(shift, Rotation::cur());
+            vec![(q_top * limb * shift, t_range)]
+        });
+
+        meta.create_gate("range decomposition", |meta| {
+            let q_decompose = meta.query_selector(q_decompose);
+            let limb = meta.query_advice(limb, Rotation::cur());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            vec![q_decompose * (acc - limb - acc_next * F::from(1 << LIMB_BITS))]
+        });
+        meta.create_gate("range top limb", |meta| {
+            let q_top = meta.query_selector(q_top);
+            let limb = meta.query_advice(limb, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            vec![q_top * (acc - limb)]
+        });
+
+        Self { q_range, q_decompose, q_top, limb, acc, shift, t_range }
+    }
+
+    pub fn load_table<F: PrimeField>(&self, layouter: &mut impl Layouter<F>) -> Result<(), plonk::Error> {
+        layouter.assign_table(
+            || "range check table",
+            |mut table| {
+                for i in 0..1 << LIMB_BITS {
+                    table.assign_cell(|| "range", self.t_range, i, || Value::known(F::from(i as u64)))?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// `0 <= x < 2^bits`. `bits` must be below the capacity of the field, otherwise the limbs
+    /// could add up to `x` plus the modulus.
+    pub fn assert_in_range<F: PrimeField>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        x: AssignedCell<F, F>,
+        bits: usize,
+    ) -> Result<(), plonk::Error> {
+        if bits == 0 || bits >= F::CAPACITY as usize {
+            return Err(plonk::Error::Synthesis);
+        }
+        let limbs = bits.div_ceil(LIMB_BITS);
+        let top_shift = match bits % LIMB_BITS {
+            0 => F::ONE,
+            top_bits => F::from(1 << (LIMB_BITS - top_bits)),
+        };
+
+        // the representation of the fields of this chapter is little-endian
+        let limb_values: Vec<Value<F>> = (0..limbs)
+            .map(|i| x.value().map(|x| F::from(x.to_repr().as_ref()[i] as u64)))
+            .collect();
+
+        layouter.assign_region(
+            || "range check",
+            |mut region| {
+                let mut acc = Value::known(F::ZERO);
+                for row in (0..limbs).rev() {
+                    self.q_range.enable(&mut region, row)?;
+                    region.assign_advice(|| "limb", self.limb, row, || limb_values[row])?;
+
+                    acc = acc * Value::known(F::from(1 << LIMB_BITS)) + limb_values[row];
+                    if row == limbs - 1 {
+                        self.q_top.enable(&mut region, row)?;
+                        region.assign_fixed(|| "shift", self.shift, row, || Value::known(top_shift))?;
+                    } else {
+                        self.q_decompose.enable(&mut region, row)?;
+                    }
+                    if row == 0 {
+                        // the limbs must add up to x itself
+                        x.copy_advice(|| "x", &mut region, self.acc, 0)?;
+                    } else {
+                        region.assign_advice(|| "acc", self.acc, row, || acc)?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// `a < b`, for `a` and `b` in `[0, 2^bits)`, which is checked as well.
+    ///
+    /// With `s = b - a` the prover shows that `lt * (2s - 1) - s` is in range: it is `s - 1`
+    /// when `lt` is 1 and `-s` when `lt` is 0, and only one of them is in `[0, 2^bits)`.
+    pub fn less_than<F: PrimeField>(
+        &self,
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        a: AssignedCell<F, F>,
+        b: AssignedCell<F, F>,
+        bits: usize,
+    ) -> Result<AssignedBool<F>, plonk::Error> {
+        let chip = &config.plonk_chip;
+        self.assert_in_range(layouter, a.clone(), bits)?;
+        self.assert_in_range(layouter, b.clone(), bits)?;
+
+        let lt_value = a.value().zip(b.value()).map(|(a, b)| {
+            let (a, b) = (a.to_repr(), b.to_repr());
+            // little-endian, compare from the most significant byte
+            if a.as_ref().iter().rev().lt(b.as_ref().iter().rev()) { F::ONE } else { F::ZERO }
+        });
+        let lt = layouter.assign_region(
+            || "less than",
+            |mut region| region.assign_advice(|| "lt", config.a, 0, || lt_value),
+        )?;
+        let lt = chip.assert_bool(config, layouter, lt)?;
+
+        let s = chip.subtract_cells(config, layouter, b, a)?;
+        let two_s_minus_one = layouter.assign_region(
+            || "2s - 1",
+            |mut region| {
+                PlonkChip::_assign_plonk_regions(&mut region, config, F::ONE.double(), F::ZERO, F::ZERO, -F::ONE, -F::ONE)?;
+
+                let s = s.copy_advice(|| "Copy a", &mut region, config.a, 0)?;
+                let c_value = s.value().map(|s| s.double() - F::ONE);
+                region.assign_advice(|| "Result", config.c, 0, || c_value)
+            },
+        )?;
+        let product = chip.multiply_cells(config, layouter, lt.cell(), two_s_minus_one)?;
+        let r = chip.subtract_cells(config, layouter, product, s)?;
+        self.assert_in_range(layouter, r, bits)?;
+        Ok(lt)
+    }
+
+    /// `lt` is `less_than(a, b)`, so that `max` and `min` of the same pair share it.
+    pub fn max<F: PrimeField>(
+        &self,
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        lt: &AssignedBool<F>,
+        a: AssignedCell<F, F>,
+        b: AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+        config.plonk_chip.select(config, layouter, lt, b, a)
+    }
+
+    /// `lt` is `less_than(a, b)`, see `max`.
+    pub fn min<F: PrimeField>(
+        &self,
+        config: &TestConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        lt: &AssignedBool<F>,
+        a: AssignedCell<F, F>,
+        b: AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+        config.plonk_chip.select(config, layouter, lt, a, b)
+    }
+}
+
+/// Public inputs: `[a < b, max(a, b), min(a, b)]` for private `a` and `b` of `bits` bits.
+pub(crate) struct ComparisonCircuit<F: Field> {
+    pub a: Value<F>,
+    pub b: Value<F>,
+    pub bits: usize,
+}
+
+impl<F: Field> PublicInputs for ComparisonCircuit<F> {
+    fn public_input_schema(&self) -> PublicInputSchema {
+        PublicInputSchema::new(["a < b", "max", "min"]).unwrap()
+    }
+}
+
+impl<F: Field + PrimeField> Circuit<F> for ComparisonCircuit<F> {
+    type Config = (TestConfig<F>, RangeCheckConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        ComparisonCircuit { a: Value::unknown(), b: Value::unknown(), bits: self.bits }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let config = TestCircuit::configure(meta);
+        let range = RangeCheckConfig::configure(meta, config.a, config.b);
+        (config, range)
+    }
+
+    fn synthesize(
+        &self,
+        (config, range): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), plonk::Error> {
+        let chip = &config.plonk_chip;
+        let expected = chip.bind_named_public_inputs(&config, &mut layouter, &self.public_input_schema())?;
+        range.load_table(&mut layouter)?;
+
+        let [a, b] = [self.a, self.b].map(|value| {
+            layouter.assign_region(
+                || "Private input",
+                |mut region| region.assign_advice(|| "Private input", config.a, 0, || value),
+            )
+        });
+        let (a, b) = (a?, b?);
+
+        let lt = range.less_than(&config, &mut layouter, a.clone(), b.clone(), self.bits)?;
+        let results = [
+            ("max", range.max(&config, &mut layouter, &lt, a.clone(), b.clone())?),
+            ("min", range.min(&config, &mut layouter, &lt, a, b)?),
+            ("a < b", lt.cell()),
+        ];
+        for (name, result) in results {
+            chip.enforce_cells_to_be_equal(&config, &mut layouter, result, expected.get(name)?)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+    use crate::tests::verify;
+
+    fn circuit(a: u64, b: u64, bits: usize) -> ComparisonCircuit<Fr> {
+        ComparisonCircuit { a: Value::known(Fr::from(a)), b: Value::known(Fr::from(b)), bits }
+    }
+
+    #[test]
+    fn test_should_compare(){
+        // an age check
+        assert!(verify(9, &circuit(17, 18, 7), &[1, 18, 17].map(Fr::from)).is_ok());
+        assert!(verify(9, &circuit(18, 18, 7), &[0, 18, 18].map(Fr::from)).is_ok());
+        assert!(verify(9, &circuit(65, 18, 7), &[0, 65, 18].map(Fr::from)).is_ok());
+        // a balance with several limbs
+        assert!(verify(9, &circuit(1_000_000, 999_999, 32), &[0, 1_000_000, 999_999].map(Fr::from)).is_ok());
+        assert!(verify(9, &circuit(0, u32::MAX as u64, 32), &[1, u32::MAX as u64, 0].map(Fr::from)).is_ok());
+    }
+
+    #[test]
+    fn test_should_not_accept_wrong_results(){
+        assert!(verify(9, &circuit(17, 18, 7), &[0, 18, 17].map(Fr::from)).is_err());
+        assert!(verify(9, &circuit(17, 18, 7), &[1, 17, 18].map(Fr::from)).is_err());
+    }
+
+    #[test]
+    fn test_should_not_accept_values_out_of_range(){
+        // 128 doesn't fit in 7 bits, even if the top limb fits in 8
+        assert!(verify(9, &circuit(128, 18, 7), &[0, 128, 18].map(Fr::from)).is_err());
+        assert!(verify(9, &circuit(17, 1 << 32, 32), &[1, 1 << 32, 17].map(Fr::from)).is_err());
+    }
+}
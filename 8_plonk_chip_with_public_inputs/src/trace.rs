@@ -0,0 +1,211 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::rc::Rc;
+
+use halo2_proofs::circuit::{AssignedCell, Value};
+
+use ff::{Field, PrimeField};
+
+use crate::PlonkChip;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Operation {
+    /// the public input at this row of the instance column
+    Public(usize),
+    Constant,
+    Add,
+    Subtract,
+    Multiply,
+    Equal,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operation::Public(row) => write!(f, "public #{}", row),
+            Operation::Constant => write!(f, "constant"),
+            Operation::Add => write!(f, "add"),
+            Operation::Subtract => write!(f, "sub"),
+            Operation::Multiply => write!(f, "mul"),
+            Operation::Equal => write!(f, "equal"),
+        }
+    }
+}
+
+/// An advice cell: region, row within the region and column. Copies of a cell into other
+/// regions aren't recorded, so an input is always the cell that some earlier step returned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct CellRef {
+    region: usize,
+    row: usize,
+    column: usize,
+}
+
+impl CellRef {
+    fn of<F: Field>(cell: &AssignedCell<F, F>) -> Self {
+        let cell = cell.cell();
+        CellRef { region: *cell.region_index, row: cell.row_offset, column: cell.column.index() }
+    }
+}
+
+impl fmt::Display for CellRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "r{}[{}].{}", self.region, self.row, self.column)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Step<F> {
+    pub op: Operation,
+    pub inputs: Vec<(CellRef, Value<F>)>,
+    pub output: Option<(CellRef, Value<F>)>,
+}
+
+/// The operations of a recording `PlonkChip`, in the order they were synthesized. Clones share
+/// the same steps, so the circuit can keep one and read it after proving.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Trace<F> {
+    steps: Rc<RefCell<Vec<Step<F>>>>,
+}
+
+impl<F: Field> Trace<F> {
+    fn push(&self, step: Step<F>) {
+        self.steps.borrow_mut().push(step);
+    }
+}
+
+/// Small values in decimal, negative ones as `-n`, anything else in hex. `?` if unknown, as in
+/// keygen.
+fn format_value<F: PrimeField>(value: &Value<F>) -> String {
+    let mut text = String::from("?");
+    value.map(|value| {
+        let small = |value: F| {
+            let repr = value.to_repr();
+            // little-endian, see `range.rs`
+            let (low, high) = repr.as_ref().split_at(8);
+            high.iter().all(|byte| *byte == 0).then(|| u64::from_le_bytes(low.try_into().unwrap()))
+        };
+        text = match (small(value), small(-value)) {
+            (Some(n), _) => n.to_string(),
+            (None, Some(n)) => format!("-{}", n),
+            (None, None) => {
+                let hex: String = value.to_repr().as_ref().iter().rev().map(|byte| format!("{:02x}", byte)).collect();
+                format!("0x{}", hex)
+            }
+        };
+    });
+    text
+}
+
+impl<F: PrimeField> Trace<F> {
+    /// One line per step: `index: operation inputs -> output`, each cell with its value.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (i, step) in self.steps.borrow().iter().enumerate() {
+            let inputs: Vec<String> =
+                step.inputs.iter().map(|(cell, value)| format!("{} = {}", cell, format_value(value))).collect();
+            write!(text, "{:>4}: {:<10} {}", i, step.op.to_string(), inputs.join(", ")).unwrap();
+            if let Some((cell, value)) = &step.output {
+                write!(text, " -> {} = {}", cell, format_value(value)).unwrap();
+            }
+            writeln!(text).unwrap();
+        }
+        text
+    }
+
+    /// A dataflow graph: a node per step and an edge, labelled with the value, from the step that
+    /// returned a cell to each step using it. Cells no step returned, such as private inputs or
+    /// results of other gadgets, get a node of their own.
+    pub fn to_dot(&self) -> String {
+        let steps = self.steps.borrow();
+        let mut producers = HashMap::new();
+        for (i, step) in steps.iter().enumerate() {
+            if let Some((cell, _)) = &step.output {
+                producers.insert(*cell, format!("step{}", i));
+            }
+        }
+
+        let mut dot = String::from("digraph plonk_chip {\n    rankdir=TB;\n    node [shape=box];\n");
+        let mut external = vec![];
+        for (i, step) in steps.iter().enumerate() {
+            let shape = match step.op {
+                Operation::Public(_) => "invhouse",
+                Operation::Equal => "diamond",
+                _ => "box",
+            };
+            writeln!(dot, "    step{} [label=\"{}\", shape={}];", i, step.op, shape).unwrap();
+            for (cell, value) in &step.inputs {
+                let source = producers.get(cell).cloned().unwrap_or_else(|| {
+                    let node = format!("cell_{}_{}_{}", cell.region, cell.row, cell.column);
+                    if !external.contains(&node) {
+                        writeln!(dot, "    {} [label=\"{}\", shape=ellipse];", node, cell).unwrap();
+                        external.push(node.clone());
+                    }
+                    node
+                });
+                writeln!(dot, "    {} -> step{} [label=\"{}\"];", source, i, format_value(value)).unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl<F: Field> PlonkChip<F> {
+    /// A copy of the chip that records every operation in `trace`.
+    pub(crate) fn recording(&self, trace: &Trace<F>) -> Self {
+        PlonkChip { trace: Some(trace.clone()), ..self.clone() }
+    }
+
+    /// Called once the region is laid out, never from inside the region closure: the floor
+    /// planner may run that more than once, which is also why `batch` keeps the steps of its
+    /// last run only.
+    pub(crate) fn record(&self, op: Operation, inputs: &[&AssignedCell<F, F>], output: Option<&AssignedCell<F, F>>) {
+        if let Some(trace) = &self.trace {
+            let entry = |cell: &AssignedCell<F, F>| (CellRef::of(cell), cell.value().cloned());
+            trace.push(Step { op, inputs: inputs.iter().map(|cell| entry(cell)).collect(), output: output.map(entry) });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+
+    #[test]
+    fn test_should_format_values(){
+        assert_eq!(format_value(&Value::known(Fr::from(8))), "8");
+        assert_eq!(format_value(&Value::known(-Fr::from(3))), "-3");
+        assert_eq!(format_value(&Value::known(Fr::from(1 << 40).square())), "0x".to_string() + &"0".repeat(43) + "1" + &"0".repeat(20));
+        assert_eq!(format_value::<Fr>(&Value::unknown()), "?");
+    }
+
+    #[test]
+    fn test_should_record_the_test_circuit(){
+        let trace = Trace::default();
+        let mut circuit = crate::tests::circuit(&["x", "y", "expected_result"], &[2]);
+        circuit.trace = Some(trace.clone());
+        let instance = vec![Fr::from(1), Fr::from(2), Fr::from(8)];
+        MockProver::run(8, &circuit, vec![instance]).unwrap().verify().unwrap();
+
+        let ops: Vec<Operation> = trace.steps.borrow().iter().map(|step| step.op).collect();
+        use Operation::*;
+        assert_eq!(ops, [Public(0), Public(1), Public(2), Multiply, Add, Multiply, Equal, Equal]);
+
+        let text = trace.to_text();
+        // aux3 == aux1 * aux2 == 2 * 4
+        assert!(text.lines().nth(5).unwrap().contains("mul        r2[0].2 = 2, r2[1].2 = 4 -> r2[2].2 = 8"), "{}", text);
+
+        let dot = trace.to_dot();
+        assert!(dot.starts_with("digraph plonk_chip {"));
+        // aux1 feeds aux2 and aux3
+        assert!(dot.contains("step3 -> step4 [label=\"2\"];"), "{}", dot);
+        assert!(dot.contains("step3 -> step5 [label=\"2\"];"), "{}", dot);
+        // z is a private input, not the result of a step
+        assert!(dot.contains("cell_1_0_0 -> step4 [label=\"2\"];"), "{}", dot);
+    }
+}
@@ -0,0 +1,437 @@
+use std::cell::Cell;
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Region, SimpleFloorPlanner, Value},
+    plonk::{self, Advice, Circuit, Column, ConstraintSystem, Fixed},
+    poly::Rotation,
+};
+
+use ff::{Field, PrimeField};
+
+use crate::PlonkInstructions;
+
+/// The coefficients of one row, see `TurboPlonkChip::new_for_advices`.
+#[derive(Clone, Copy, Debug)]
+struct Coefficients<F> {
+    ql: F,
+    qr: F,
+    qo: F,
+    q4: F,
+    qm: F,
+    qc: F,
+    q_next: F,
+}
+
+impl<F: Field> Coefficients<F> {
+    fn zero() -> Self {
+        Self { ql: F::ZERO, qr: F::ZERO, qo: F::ZERO, q4: F::ZERO, qm: F::ZERO, qc: F::ZERO, q_next: F::ZERO }
+    }
+}
+
+/// `PlonkChip` with a fourth wire `d` and, optionally, the `d` of the next row:
+///
+/// `ql * a + qr * b + qo * c + q4 * d + qm * a * b + qc + q_next * d_next = 0`
+///
+/// With the next-row term a row can add four values and leave the result in the `d` of the next
+/// row, where it is the accumulator of the following addition.
+#[derive(Clone, Debug)]
+pub(crate) struct TurboPlonkChip<F> {
+    _ph: PhantomData<F>,
+    ql: Column<Fixed>,
+    qr: Column<Fixed>,
+    qo: Column<Fixed>,
+    q4: Column<Fixed>,
+    qm: Column<Fixed>,
+    qc: Column<Fixed>,
+    q_next: Option<Column<Fixed>>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct TurboConfig<F: Field> {
+    _ph: PhantomData<F>,
+    pub turbo_chip: TurboPlonkChip<F>,
+    a: Column<Advice>,
+    b: Column<Advice>,
+    c: Column<Advice>,
+    d: Column<Advice>,
+}
+
+impl<F: Field> TurboPlonkChip<F> {
+    fn new_for_advices(
+        meta: &mut ConstraintSystem<F>,
+        a: Column<Advice>,
+        b: Column<Advice>,
+        c: Column<Advice>,
+        d: Column<Advice>,
+        with_next: bool,
+    ) -> Self {
+        let ql = meta.fixed_column();
+        let qr = meta.fixed_column();
+        let qo = meta.fixed_column();
+        let q4 = meta.fixed_column();
+        let qm = meta.fixed_column();
+        let qc = meta.fixed_column();
+        let q_next = with_next.then(|| meta.fixed_column());
+
+        meta.create_gate("TurboPlonk Gate", |meta| {
+            let a_ = meta.query_advice(a, Rotation::cur());
+            let b_ = meta.query_advice(b, Rotation::cur());
+            let c_ = meta.query_advice(c, Rotation::cur());
+            let d_ = meta.query_advice(d, Rotation::cur());
+
+            let ql_ = meta.query_fixed(ql, Rotation::cur());
+            let qr_ = meta.query_fixed(qr, Rotation::cur());
+            let qo_ = meta.query_fixed(qo, Rotation::cur());
+            let q4_ = meta.query_fixed(q4, Rotation::cur());
+            let qm_ = meta.query_fixed(qm, Rotation::cur());
+            let qc_ = meta.query_fixed(qc, Rotation::cur());
+
+            let gate = a_.clone() * ql_ + b_.clone() * qr_ + c_ * qo_ + d_ * q4_ + a_ * b_ * qm_ + qc_;
+            match q_next {
+                Some(q_next) => {
+                    let d_next = meta.query_advice(d, Rotation::next());
+                    let q_next_ = meta.query_fixed(q_next, Rotation::cur());
+                    vec![gate + q_next_ * d_next]
+                }
+                None => vec![gate],
+            }
+        });
+
+        Self { _ph: PhantomData, ql, qr, qo, q4, qm, qc, q_next }
+    }
+
+    fn _assign_turbo_row(&self, region: &mut Region<F>, row: usize, q: Coefficients<F>) -> Result<(), plonk::Error> {
+        region.assign_fixed(|| "Ql", self.ql, row, || Value::known(q.ql))?;
+        region.assign_fixed(|| "Qr", self.qr, row, || Value::known(q.qr))?;
+        region.assign_fixed(|| "Qo", self.qo, row, || Value::known(q.qo))?;
+        region.assign_fixed(|| "Q4", self.q4, row, || Value::known(q.q4))?;
+        region.assign_fixed(|| "Qm", self.qm, row, || Value::known(q.qm))?;
+        region.assign_fixed(|| "Qc", self.qc, row, || Value::known(q.qc))?;
+        match self.q_next {
+            Some(q_next) => region.assign_fixed(|| "Qnext", q_next, row, || Value::known(q.q_next)).map(|_| ()),
+            // the gate has no next-row term to use
+            None if q.q_next != F::ZERO => Err(plonk::Error::Synthesis),
+            None => Ok(()),
+        }
+    }
+
+    /// `lhs op rhs` in a single row, the result goes to `c`.
+    fn binary_op(
+        &self,
+        config: &TurboConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        lhs: AssignedCell<F, F>,
+        rhs: AssignedCell<F, F>,
+        q: Coefficients<F>,
+        op: impl Fn(F, F) -> F,
+    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+        layouter.assign_region(
+            || "turbo binary op",
+            |mut region| {
+                self._assign_turbo_row(&mut region, 0, q)?;
+
+                let a = lhs.copy_advice(|| "Copy a", &mut region, config.a, 0)?;
+                let b = rhs.copy_advice(|| "Copy b", &mut region, config.b, 0)?;
+                let c_value = a.value().zip(b.value()).map(|(a, b)| op(*a, *b));
+                region.assign_advice(|| "Result", config.c, 0, || c_value)
+            },
+        )
+    }
+
+    /// Adds up `terms` in a single region: 3 values on the first row and 2 on each of the others,
+    /// or with the next-row term 4 and 3 plus a last row for the result.
+    ///
+    /// Without the next-row term each row writes its sum to `d` and the next row copies it to
+    /// `a`. With it, each row writes its sum to the `d` of the next row, which is added there
+    /// without a copy.
+    pub(crate) fn sum_cells(
+        &self,
+        config: &TurboConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        terms: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+        match terms {
+            [] => return Err(plonk::Error::Synthesis),
+            [term] => return Ok(term.clone()),
+            _ => {}
+        }
+        let next = self.q_next.is_some();
+        let wires = [config.a, config.b, config.c, config.d];
+
+        layouter.assign_region(
+            || "sum",
+            |mut region| {
+                let mut terms = terms.iter();
+                let mut acc: Option<AssignedCell<F, F>> = None;
+                let mut row = 0;
+                loop {
+                    // ql, qr, qo, q4 for a, b, c, d
+                    let mut q = [F::ZERO; 4];
+                    let mut sum = Value::known(F::ZERO);
+                    let inputs = match (&acc, next) {
+                        (None, true) => 0..4,
+                        (None, false) => 0..3,
+                        (Some(acc), true) => {
+                            // already in `d`, written by the previous row
+                            q[3] = F::ONE;
+                            sum = acc.value().cloned();
+                            0..3
+                        }
+                        (Some(acc), false) => {
+                            acc.copy_advice(|| "Accumulator", &mut region, config.a, row)?;
+                            q[0] = F::ONE;
+                            sum = acc.value().cloned();
+                            1..3
+                        }
+                    };
+                    for (i, term) in inputs.zip(terms.by_ref()) {
+                        term.copy_advice(|| "Term", &mut region, wires[i], row)?;
+                        q[i] = F::ONE;
+                        sum = sum + term.value().cloned();
+                    }
+
+                    let mut coefficients = Coefficients { ql: q[0], qr: q[1], qo: q[2], q4: q[3], ..Coefficients::zero() };
+                    let result_row = if next {
+                        coefficients.q_next = -F::ONE;
+                        row + 1
+                    } else {
+                        coefficients.q4 = -F::ONE;
+                        row
+                    };
+                    self._assign_turbo_row(&mut region, row, coefficients)?;
+                    let result = region.assign_advice(|| "Sum", config.d, result_row, || sum)?;
+
+                    if terms.len() == 0 {
+                        return Ok(result);
+                    }
+                    acc = Some(result);
+                    row += 1;
+                }
+            },
+        )
+    }
+}
+
+impl<F: Field> PlonkInstructions<F> for TurboPlonkChip<F> {
+    type Config = TurboConfig<F>;
+
+    fn multiply_cells(
+        &self,
+        config: &Self::Config,
+        layouter: &mut impl Layouter<F>,
+        lhs: AssignedCell<F, F>,
+        rhs: AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+        let q = Coefficients { qm: F::ONE, qo: -F::ONE, ..Coefficients::zero() };
+        self.binary_op(config, layouter, lhs, rhs, q, |a, b| a * b)
+    }
+
+    fn add_cells(
+        &self,
+        config: &Self::Config,
+        layouter: &mut impl Layouter<F>,
+        lhs: AssignedCell<F, F>,
+        rhs: AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+        let q = Coefficients { ql: F::ONE, qr: F::ONE, qo: -F::ONE, ..Coefficients::zero() };
+        self.binary_op(config, layouter, lhs, rhs, q, |a, b| a + b)
+    }
+
+    fn subtract_cells(
+        &self,
+        config: &Self::Config,
+        layouter: &mut impl Layouter<F>,
+        lhs: AssignedCell<F, F>,
+        rhs: AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+        let q = Coefficients { ql: F::ONE, qr: -F::ONE, qo: -F::ONE, ..Coefficients::zero() };
+        self.binary_op(config, layouter, lhs, rhs, q, |a, b| a - b)
+    }
+
+    fn new_constant_cell(
+        &self,
+        config: &Self::Config,
+        layouter: &mut impl Layouter<F>,
+        constant_value: F,
+    ) -> Result<AssignedCell<F, F>, plonk::Error> {
+        layouter.assign_region(
+            || "constant",
+            |mut region| {
+                let q = Coefficients { qo: -F::ONE, qc: constant_value, ..Coefficients::zero() };
+                self._assign_turbo_row(&mut region, 0, q)?;
+                region.assign_advice(|| "Result", config.c, 0, || Value::known(constant_value))
+            },
+        )
+    }
+
+    fn enforce_cells_to_be_equal(
+        &self,
+        config: &Self::Config,
+        layouter: &mut impl Layouter<F>,
+        lhs: AssignedCell<F, F>,
+        rhs: AssignedCell<F, F>,
+    ) -> Result<(), plonk::Error> {
+        layouter.assign_region(
+            || "equality",
+            |mut region| {
+                let q = Coefficients { ql: F::ONE, qr: -F::ONE, ..Coefficients::zero() };
+                self._assign_turbo_row(&mut region, 0, q)?;
+                lhs.copy_advice(|| "Copy a", &mut region, config.a, 0)?;
+                rhs.copy_advice(|| "Copy b", &mut region, config.b, 0)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+/// `(x*y + z) * (x*y)`, the statement of `TestCircuit`, written once for any chip.
+pub(crate) fn test_polynomial<F: Field, C: PlonkInstructions<F>>(
+    chip: &C,
+    config: &C::Config,
+    layouter: &mut impl Layouter<F>,
+    x: AssignedCell<F, F>,
+    y: AssignedCell<F, F>,
+    z: AssignedCell<F, F>,
+) -> Result<AssignedCell<F, F>, plonk::Error> {
+    let aux1 = chip.multiply_cells(config, layouter, x, y)?;
+    let aux2 = chip.add_cells(config, layouter, aux1.clone(), z)?;
+    chip.multiply_cells(config, layouter, aux1, aux2)
+}
+
+/// Proves `sum(terms) == sum` and `(t0*t1 + t2) * (t0*t1) == product` on the TurboPlonk chip,
+/// `NEXT` choosing whether the gate has the next-row term.
+pub(crate) struct TurboCircuit<F: Field, const NEXT: bool> {
+    pub terms: Vec<Value<F>>,
+    pub sum: F,
+    pub product: F,
+    /// rows used by the "sum" region; set by `synthesize`
+    pub sum_rows: Cell<usize>,
+}
+
+/// Rows laid out by `sum_cells` to add `n` values, `NEXT` as in `TurboCircuit`.
+pub(crate) fn measure<const NEXT: bool>(n: u64) -> usize {
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    let circuit = TurboCircuit::<Fr, NEXT> {
+        terms: (1..=n).map(|t| Value::known(Fr::from(t))).collect(),
+        sum: Fr::from(n * (n + 1) / 2),
+        // (1*2 + 3) * (1*2)
+        product: Fr::from(10),
+        sum_rows: Cell::new(0),
+    };
+    MockProver::run(8, &circuit, vec![]).unwrap().verify().unwrap();
+    circuit.sum_rows.get()
+}
+
+impl<F: PrimeField, const NEXT: bool> Circuit<F> for TurboCircuit<F, NEXT> {
+    type Config = TurboConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self {
+        TurboCircuit {
+            terms: vec![Value::unknown(); self.terms.len()],
+            sum: self.sum,
+            product: self.product,
+            sum_rows: Cell::new(0),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let c = meta.advice_column();
+        let d = meta.advice_column();
+
+        meta.enable_equality(a);
+        meta.enable_equality(b);
+        meta.enable_equality(c);
+        meta.enable_equality(d);
+
+        let turbo_chip = TurboPlonkChip::new_for_advices(meta, a, b, c, d, NEXT);
+
+        TurboConfig { _ph: PhantomData, turbo_chip, a, b, c, d }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), plonk::Error> {
+        let chip = &config.turbo_chip;
+        if self.terms.len() < 3 {
+            return Err(plonk::Error::Synthesis);
+        }
+
+        let terms = layouter.assign_region(
+            || "Private inputs",
+            |mut region| {
+                self.terms
+                    .iter()
+                    .enumerate()
+                    .map(|(row, value)| region.assign_advice(|| "Private input", config.a, row, || *value))
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )?;
+
+        let sum = chip.sum_cells(&config, &mut layouter, &terms)?;
+        // the sum is on the last row of its region
+        self.sum_rows.set(sum.cell().row_offset + 1);
+        let expected = chip.new_constant_cell(&config, &mut layouter, self.sum)?;
+        chip.enforce_cells_to_be_equal(&config, &mut layouter, sum, expected)?;
+
+        let [x, y, z] = [0, 1, 2].map(|i| terms[i].clone());
+        let product = test_polynomial(chip, &config, &mut layouter, x, y, z)?;
+        let expected = chip.new_constant_cell(&config, &mut layouter, self.product)?;
+        chip.enforce_cells_to_be_equal(&config, &mut layouter, product, expected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+
+    fn circuit<const NEXT: bool>(terms: &[u64], sum: u64, product: u64) -> TurboCircuit<Fr, NEXT> {
+        TurboCircuit {
+            terms: terms.iter().map(|t| Value::known(Fr::from(*t))).collect(),
+            sum: Fr::from(sum),
+            product: Fr::from(product),
+            sum_rows: Cell::new(0),
+        }
+    }
+
+    #[test]
+    fn test_should_add_any_number_of_terms(){
+        for n in 3..12u64 {
+            let terms: Vec<u64> = (1..=n).collect();
+            // (1*2 + 3) * (1*2)
+            assert!(MockProver::run(6, &circuit::<false>(&terms, n * (n + 1) / 2, 10), vec![]).unwrap().verify().is_ok(), "{} terms", n);
+            assert!(MockProver::run(6, &circuit::<true>(&terms, n * (n + 1) / 2, 10), vec![]).unwrap().verify().is_ok(), "{} terms", n);
+        }
+    }
+
+    #[test]
+    fn test_should_not_accept_wrong_results(){
+        let terms = [1, 2, 3, 4, 5, 6, 7];
+        assert!(MockProver::run(6, &circuit::<false>(&terms, 27, 10), vec![]).unwrap().verify().is_err());
+        assert!(MockProver::run(6, &circuit::<true>(&terms, 27, 10), vec![]).unwrap().verify().is_err());
+        assert!(MockProver::run(6, &circuit::<true>(&terms, 28, 11), vec![]).unwrap().verify().is_err());
+    }
+
+    #[test]
+    fn test_should_use_fewer_rows_with_next_row_term(){
+        assert_eq!((measure::<false>(7), measure::<true>(7)), (3, 3));
+        assert_eq!((measure::<false>(10), measure::<true>(10)), (5, 4));
+        assert_eq!(measure::<true>(4), 2);
+        for n in 3..20 {
+            // see `sum_cells`
+            assert_eq!(measure::<false>(n), 1 + (n as usize - 3).div_ceil(2), "{} terms", n);
+            assert_eq!(measure::<true>(n), 2 + (n as usize).saturating_sub(4).div_ceil(3), "{} terms", n);
+        }
+    }
+}
@@ -146,17 +146,17 @@ impl<F: PrimeField> U8Chip<F> {
 }
 
 impl<F: Field + PrimeField> TestCircuit<F>{
-    fn set_lookup_table_u8(&self, layouter: &mut impl Layouter<F>, config: &TestConfig<F>){
-        let _ = layouter.assign_table(|| "Range Check u8", |mut table| {
+    fn set_lookup_table_u8(&self, layouter: &mut impl Layouter<F>, config: &TestConfig<F>) -> Result<(), plonk::Error> {
+        layouter.assign_table(|| "Range Check u8", |mut table| {
             for i in 0..256u128 {
                 table.assign_cell(|| "Range check u8 table", config.u8_chip.t_range, i as usize, ||Value::known(F::from_u128(i)))?;
             }
             Ok(())
-        });
+        })
     }
 
-    fn set_lookup_table_xor(&self, layouter: &mut impl Layouter<F>, config: &TestConfig<F>){
-        let _ = layouter.assign_table(|| "bit xor table", |mut table| {
+    fn set_lookup_table_xor(&self, layouter: &mut impl Layouter<F>, config: &TestConfig<F>) -> Result<(), plonk::Error> {
+        layouter.assign_table(|| "bit xor table", |mut table| {
             // TODO: please refactor this hurts
             table.assign_cell(|| "xs0", config.u8_chip.t_selector, 0, ||Value::known(F::ZERO))?;
             table.assign_cell(|| "xs1", config.u8_chip.t_selector, 1, ||Value::known(F::ZERO))?;
@@ -179,16 +179,18 @@ impl<F: Field + PrimeField> TestCircuit<F>{
             table.assign_cell(|| "xa3", config.u8_chip.t_result, 3, ||Value::known(F::ZERO))?;
 
             Ok(())
-        });
+        })
     }
 
     fn add_decomposed_row_to_region(&self, region: &mut Region<F>,
-                                    config: &TestConfig<F>, row: [ExampleRow<F>; 3], index: usize){
-        let _ = config.u8_chip.q_decomposed.enable(region, 0);
-        let _ = region.assign_advice(||"Valor de prueba", config.advice, index, || row[index].advice);
+                                    config: &TestConfig<F>, row: [ExampleRow<F>; 3], index: usize) -> Result<(), plonk::Error> {
+        // every row of the region is range checked and decomposed, not only the first one
+        config.u8_chip.q_decomposed.enable(region, index)?;
+        region.assign_advice(||"Valor de prueba", config.advice, index, || row[index].advice)?;
         for i in 0..8 {
-            let _ = region.assign_advice(||"Descomposicion en bits", config.u8_chip.bits[i], index, || row[index].bits[i]);
+            region.assign_advice(||"Descomposicion en bits", config.u8_chip.bits[i], index, || row[index].bits[i])?;
         }
+        Ok(())
     }
 }
 
@@ -220,17 +222,16 @@ impl<F: Field + PrimeField> Circuit<F> for TestCircuit<F> {
         config: Self::Config,
         mut layouter: impl Layouter<F>,
     ) -> Result<(), plonk::Error> {
-        self.set_lookup_table_u8(&mut layouter, &config);
-        self.set_lookup_table_xor(&mut layouter, &config);
-
-        let _ = layouter.assign_region(||"Pruebita xor", |mut region| {
-            let _ = config.u8_chip.q_xor.enable(&mut region, 0);
-            self.add_decomposed_row_to_region(&mut region, &config, self.rows, 0);
-            self.add_decomposed_row_to_region(&mut region, &config, self.rows, 1);
-            self.add_decomposed_row_to_region(&mut region, &config, self.rows, 2);
+        self.set_lookup_table_u8(&mut layouter, &config)?;
+        self.set_lookup_table_xor(&mut layouter, &config)?;
+
+        layouter.assign_region(||"Pruebita xor", |mut region| {
+            config.u8_chip.q_xor.enable(&mut region, 0)?;
+            self.add_decomposed_row_to_region(&mut region, &config, self.rows, 0)?;
+            self.add_decomposed_row_to_region(&mut region, &config, self.rows, 1)?;
+            self.add_decomposed_row_to_region(&mut region, &config, self.rows, 2)?;
             Ok(())
-        });
-        Ok(())
+        })
     }
 }
 
@@ -349,4 +350,25 @@ mod test {
         let prover = MockProver::run(16, &circuit, vec![]).unwrap();
         prover.verify().unwrap();
     }
-}
\ No newline at end of file
+
+    /// `advice` with the bits of `value`, least significant first.
+    fn row(advice: u64, value: u8) -> super::ExampleRow<halo2_proofs::halo2curves::bn256::Fr> {
+        use halo2_proofs::halo2curves::bn256::Fr;
+        use super::*;
+        ExampleRow {
+            advice: Value::known(Fr::from(advice)),
+            bits: std::array::from_fn(|i| Value::known(Fr::from(((value >> i) & 1) as u64))),
+        }
+    }
+
+    #[test]
+    fn test_should_check_the_decomposition_of_every_row(){
+        use halo2_proofs::halo2curves::bn256::Fr;
+        use super::*;
+        let verify = |rows| MockProver::run(16, &TestCircuit::<Fr> { _ph: PhantomData, rows }, vec![]).unwrap().verify();
+        assert!(verify([row(7, 7), row(8, 8), row(15, 15)]).is_ok());
+        // the bits still xor, but they aren't the decomposition of the value in that row
+        assert!(verify([row(7, 7), row(9, 8), row(15, 15)]).is_err());
+        assert!(verify([row(7, 7), row(8, 8), row(14, 15)]).is_err());
+    }
+}
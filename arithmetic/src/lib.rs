@@ -0,0 +1,288 @@
+//! The vertical `ArithmeticChip`, shared by the chapters from `4_regions` on.
+
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Chip, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Instance, Selector},
+    poly::Rotation,
+};
+
+use ff::Field;
+
+/// The operations a circuit needs to do arithmetic over cells, independently of how the chip
+/// lays them out.
+pub trait ArithmeticInstructions<F: Field>: Chip<F> {
+    /// A number inside the circuit
+    type Num;
+
+    /// A new free variable, known only to the prover
+    fn load_private(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<Self::Num, Error>;
+
+    /// `a * b`
+    fn mul(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        a: &Self::Num,
+        b: &Self::Num,
+    ) -> Result<Self::Num, Error>;
+
+    /// `a + b`
+    fn add(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        a: &Self::Num,
+        b: &Self::Num,
+    ) -> Result<Self::Num, Error>;
+
+    /// `a == b`
+    fn constrain_equal(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        a: &Self::Num,
+        b: &Self::Num,
+    ) -> Result<(), Error>;
+
+    /// `num` is the public input at `row`. Fails with `Error::Synthesis` if the chip has no
+    /// instance column.
+    fn expose_public(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        num: &Self::Num,
+        row: usize,
+    ) -> Result<(), Error>;
+}
+
+#[derive(Clone, Debug)]
+pub struct ArithmeticConfig {
+    pub advice: Column<Advice>,
+    /// only needed by `expose_public`
+    pub instance: Option<Column<Instance>>,
+    pub q_mul: Selector,
+    pub q_add: Selector,
+}
+
+/// Every operation is its own 3-row region in a single advice column: the operands are copied
+/// into the first two rows and the result goes in the third.
+#[derive(Clone, Debug)]
+pub struct ArithmeticChip<F: Field> {
+    config: ArithmeticConfig,
+    _ph: PhantomData<F>,
+}
+
+impl<F: Field> Chip<F> for ArithmeticChip<F> {
+    type Config = ArithmeticConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: Field> ArithmeticChip<F> {
+    pub fn construct(config: ArithmeticConfig) -> Self {
+        Self { config, _ph: PhantomData }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: Column<Advice>,
+        instance: Option<Column<Instance>>,
+    ) -> ArithmeticConfig {
+        let q_mul = meta.complex_selector();
+        let q_add = meta.complex_selector();
+        meta.enable_equality(advice);
+        if let Some(instance) = instance {
+            meta.enable_equality(instance);
+        }
+
+        meta.create_gate("vertical-mul", |meta| {
+            let w0 = meta.query_advice(advice, Rotation(0));
+            let w1 = meta.query_advice(advice, Rotation(1));
+            let w2 = meta.query_advice(advice, Rotation(2));
+            let q_mul = meta.query_selector(q_mul);
+            vec![q_mul * (w0 * w1 - w2)]
+        });
+
+        meta.create_gate("vertical-add", |meta| {
+            let w0 = meta.query_advice(advice, Rotation(0));
+            let w1 = meta.query_advice(advice, Rotation(1));
+            let w2 = meta.query_advice(advice, Rotation(2));
+            let q_add = meta.query_selector(q_add);
+            vec![q_add * (w0 + w1 - w2)]
+        });
+
+        ArithmeticConfig { advice, instance, q_mul, q_add }
+    }
+
+    /// The factors are copied with `copy_advice`, which also adds an equality constraint with
+    /// the original cell: without it every region would be independent and the prover could
+    /// use any value as an operand.
+    fn binary_op(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        name: &'static str,
+        selector: Selector,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        op: impl Fn(F, F) -> F,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = self.config();
+        layouter.assign_region(
+            || name,
+            |mut region| {
+                let w0 = a.copy_advice(|| "assign w0", &mut region, config.advice, 0)?;
+                let w1 = b.copy_advice(|| "assign w1", &mut region, config.advice, 1)?;
+
+                let w2 = w0.value().zip(w1.value()).map(|(w0, w1)| op(*w0, *w1));
+                let w2 = region.assign_advice(|| "assign w2", config.advice, 2, || w2)?;
+                selector.enable(&mut region, 0)?;
+                Ok(w2)
+            },
+        )
+    }
+}
+
+impl<F: Field> ArithmeticInstructions<F> for ArithmeticChip<F> {
+    type Num = AssignedCell<F, F>;
+
+    fn load_private(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<Self::Num, Error> {
+        layouter.assign_region(
+            || "free variable",
+            |mut region| region.assign_advice(|| "assign w0", self.config.advice, 0, || value),
+        )
+    }
+
+    fn mul(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        a: &Self::Num,
+        b: &Self::Num,
+    ) -> Result<Self::Num, Error> {
+        self.binary_op(layouter, "mul", self.config.q_mul, a, b, |a, b| a * b)
+    }
+
+    fn add(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        a: &Self::Num,
+        b: &Self::Num,
+    ) -> Result<Self::Num, Error> {
+        self.binary_op(layouter, "add", self.config.q_add, a, b, |a, b| a + b)
+    }
+
+    fn constrain_equal(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        a: &Self::Num,
+        b: &Self::Num,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "constrain equal",
+            |mut region| region.constrain_equal(a.cell(), b.cell()),
+        )
+    }
+
+    fn expose_public(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        num: &Self::Num,
+        row: usize,
+    ) -> Result<(), Error> {
+        let instance = self.config.instance.ok_or(Error::Synthesis)?;
+        layouter.constrain_instance(num.cell(), instance, row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        halo2curves::bn256::Fr,
+        plonk::Circuit,
+    };
+
+    use super::*;
+
+    /// Exposes `a * b + a` and constrains `a * b` to be `b * a`.
+    struct MulAddCircuit {
+        a: Value<Fr>,
+        b: Value<Fr>,
+    }
+
+    /// `MulAddCircuit` without an instance column.
+    struct PrivateCircuit;
+
+    impl Circuit<Fr> for PrivateCircuit {
+        type Config = ArithmeticConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            PrivateCircuit
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let advice = meta.advice_column();
+            ArithmeticChip::configure(meta, advice, None)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+            let chip = ArithmeticChip::<Fr>::construct(config);
+            let a = chip.load_private(&mut layouter, Value::known(Fr::from(3)))?;
+            let square = chip.mul(&mut layouter, &a, &a)?;
+            chip.expose_public(&mut layouter, &square, 0)
+        }
+    }
+
+    impl Circuit<Fr> for MulAddCircuit {
+        type Config = ArithmeticConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MulAddCircuit { a: Value::unknown(), b: Value::unknown() }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let advice = meta.advice_column();
+            let instance = meta.instance_column();
+            ArithmeticChip::configure(meta, advice, Some(instance))
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+            let chip = ArithmeticChip::<Fr>::construct(config);
+            let a = chip.load_private(&mut layouter, self.a)?;
+            let b = chip.load_private(&mut layouter, self.b)?;
+            let ab = chip.mul(&mut layouter, &a, &b)?;
+            let ba = chip.mul(&mut layouter, &b, &a)?;
+            chip.constrain_equal(&mut layouter, &ab, &ba)?;
+            let result = chip.add(&mut layouter, &ab, &a)?;
+            chip.expose_public(&mut layouter, &result, 0)
+        }
+    }
+
+    #[test]
+    fn test_should_compose_instructions(){
+        let circuit = MulAddCircuit { a: Value::known(Fr::from(3)), b: Value::known(Fr::from(4)) };
+        let prover = MockProver::run(8, &circuit, vec![vec![Fr::from(15)]]).unwrap();
+        prover.verify().unwrap();
+
+        let prover = MockProver::run(8, &circuit, vec![vec![Fr::from(12)]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // nowhere to expose the result
+        assert!(MockProver::run(8, &PrivateCircuit, vec![]).is_err());
+    }
+}